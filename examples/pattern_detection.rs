@@ -10,31 +10,31 @@ fn main() -> Result<()> {
         Trade {
             price: dec!(50000.0),
             quantity: dec!(0.1),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1000,
         },
         Trade {
             price: dec!(50000.0),
             quantity: dec!(0.12),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1001,
         },
         Trade {
             price: dec!(50000.0),
             quantity: dec!(0.11),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1002,
         },
         Trade {
             price: dec!(50000.0),
             quantity: dec!(0.1),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1003,
         },
         Trade {
             price: dec!(50000.0),
             quantity: dec!(0.13),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1004,
         },
     ];
@@ -137,19 +137,19 @@ fn main() -> Result<()> {
         Trade {
             price: dec!(50000.0),
             quantity: dec!(5.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1000,
         },
         Trade {
             price: dec!(50000.2),
             quantity: dec!(4.5),
-            side: "sell".to_string(),
+            side: Side::Sell,
             timestamp: 1001,
         },
         Trade {
             price: dec!(50000.1),
             quantity: dec!(6.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1002,
         },
     ];
@@ -160,7 +160,7 @@ fn main() -> Result<()> {
     if !absorption.is_empty() {
         println!("  ⚠️  Absorption detected!");
         for pattern in absorption {
-            if let patterns::Pattern::Absorption { price, volume } = pattern {
+            if let patterns::Pattern::Absorption { price, volume, .. } = pattern {
                 println!("    Large volume ({}) absorbed near ${}", volume, price);
                 println!("    Price barely moved - strong buyer/seller present");
             }
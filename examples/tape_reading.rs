@@ -10,49 +10,49 @@ fn main() -> Result<()> {
         Trade {
             price: dec!(50000.0),
             quantity: dec!(1.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435200,
         },
         Trade {
             price: dec!(50001.0),
             quantity: dec!(0.5),
-            side: "sell".to_string(),
+            side: Side::Sell,
             timestamp: 1696435201,
         },
         Trade {
             price: dec!(50002.0),
             quantity: dec!(2.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435202,
         },
         Trade {
             price: dec!(50003.0),
             quantity: dec!(0.3),
-            side: "sell".to_string(),
+            side: Side::Sell,
             timestamp: 1696435203,
         },
         Trade {
             price: dec!(50004.0),
             quantity: dec!(1.5),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435204,
         },
         Trade {
             price: dec!(50005.0),
             quantity: dec!(0.8),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435205,
         },
         Trade {
             price: dec!(50004.5),
             quantity: dec!(0.4),
-            side: "sell".to_string(),
+            side: Side::Sell,
             timestamp: 1696435206,
         },
         Trade {
             price: dec!(50006.0),
             quantity: dec!(10.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435207,
         }, // Block trade
     ];
@@ -107,7 +107,11 @@ fn main() -> Result<()> {
         for trade in blocks {
             println!(
                 "    {} {} @ ${} (size: {})",
-                if trade.side == "buy" { "BUY " } else { "SELL" },
+                match trade.side {
+                    Side::Buy => "BUY ",
+                    Side::Sell => "SELL",
+                    Side::Unknown => "????",
+                },
                 trade.quantity,
                 trade.price,
                 trade.quantity
@@ -0,0 +1,284 @@
+//! Bar Building Module
+//!
+//! This module aggregates a trade stream into OHLCV bars sampled by time,
+//! trade count, traded volume or notional value. Bars can be built in batch
+//! with [`build_bars`] or incrementally with [`BarBuilder`].
+
+use crate::types::{Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// How bars are sampled
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BarSpec {
+    /// Fixed time interval in the trades' timestamp unit, aligned to
+    /// multiples of the interval
+    Time(i64),
+    /// Fixed number of trades
+    Tick(usize),
+    /// Traded quantity reaches the threshold
+    Volume(Decimal),
+    /// Traded notional (price * quantity) reaches the threshold
+    Dollar(Decimal),
+}
+
+/// An OHLCV bar with order flow statistics
+///
+/// Volume and dollar bars close on the trade that reaches the threshold,
+/// without splitting it, so a bar may overshoot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Bar {
+    /// Interval start for time bars, first trade timestamp otherwise
+    pub start: i64,
+    /// Interval end (exclusive, capped at `i64::MAX`) for time bars, last
+    /// trade timestamp otherwise
+    pub end: i64,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume: Decimal,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    /// Same as [`crate::metrics::calculate_delta`] over the bar's trades
+    pub delta: Decimal,
+    pub notional: Decimal,
+    /// Volume-weighted average price (close when the bar has no volume)
+    pub vwap: Decimal,
+    pub trade_count: usize,
+}
+
+impl Bar {
+    fn open_with(trade: &Trade, start: i64, end: i64) -> Self {
+        let mut bar = Bar {
+            start,
+            end,
+            open: trade.price,
+            high: trade.price,
+            low: trade.price,
+            close: trade.price,
+            volume: dec!(0),
+            buy_volume: dec!(0),
+            sell_volume: dec!(0),
+            delta: dec!(0),
+            notional: dec!(0),
+            vwap: trade.price,
+            trade_count: 0,
+        };
+        bar.add(trade);
+        bar
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        self.high = self.high.max(trade.price);
+        self.low = self.low.min(trade.price);
+        self.close = trade.price;
+        self.volume += trade.quantity;
+        match trade.side {
+            Side::Buy => self.buy_volume += trade.quantity,
+            Side::Sell => self.sell_volume += trade.quantity,
+            Side::Unknown => {}
+        }
+        self.delta += trade.signed_quantity();
+        self.notional += trade.price * trade.quantity;
+        self.vwap = if self.volume > dec!(0) {
+            self.notional / self.volume
+        } else {
+            self.close
+        };
+        self.trade_count += 1;
+    }
+}
+
+/// Incremental bar builder
+#[derive(Debug, Clone)]
+pub struct BarBuilder {
+    spec: BarSpec,
+    current: Option<Bar>,
+}
+
+impl BarBuilder {
+    /// Create a builder for the given sampling
+    #[must_use]
+    pub fn new(spec: BarSpec) -> Self {
+        Self {
+            spec,
+            current: None,
+        }
+    }
+
+    /// Feed one trade
+    ///
+    /// # Returns
+    /// The bar completed by this trade, if any. For time bars that is the
+    /// previous bar, closed when a trade arrives in a later interval.
+    pub fn update(&mut self, trade: &Trade) -> Option<Bar> {
+        match self.spec {
+            BarSpec::Time(interval) => {
+                let interval = interval.max(1);
+                let start = trade.timestamp.div_euclid(interval) * interval;
+                match &mut self.current {
+                    Some(bar) if start <= bar.start => {
+                        bar.add(trade);
+                        None
+                    }
+                    _ => self.current.replace(Bar::open_with(
+                        trade,
+                        start,
+                        start.saturating_add(interval),
+                    )),
+                }
+            }
+            BarSpec::Tick(_) | BarSpec::Volume(_) | BarSpec::Dollar(_) => {
+                match &mut self.current {
+                    Some(bar) => {
+                        bar.add(trade);
+                        bar.end = trade.timestamp;
+                    }
+                    None => {
+                        self.current =
+                            Some(Bar::open_with(trade, trade.timestamp, trade.timestamp));
+                    }
+                }
+                let full = self.current.as_ref().is_some_and(|bar| match self.spec {
+                    BarSpec::Tick(n) => bar.trade_count >= n.max(1),
+                    BarSpec::Volume(v) => bar.volume >= v,
+                    BarSpec::Dollar(d) => bar.notional >= d,
+                    BarSpec::Time(_) => false,
+                });
+                if full {
+                    self.current.take()
+                } else {
+                    None
+                }
+            }
+        }
+    }
+
+    /// The bar being built, if any
+    #[must_use]
+    pub fn current(&self) -> Option<&Bar> {
+        self.current.as_ref()
+    }
+
+    /// Close and return the partial bar
+    pub fn flush(&mut self) -> Option<Bar> {
+        self.current.take()
+    }
+}
+
+/// Build bars from a trade slice (should be sorted by timestamp)
+///
+/// The final, possibly incomplete, bar is included.
+#[must_use]
+pub fn build_bars(trades: &[Trade], spec: BarSpec) -> Vec<Bar> {
+    let mut builder = BarBuilder::new(spec);
+    let mut bars: Vec<Bar> = trades.iter().filter_map(|t| builder.update(t)).collect();
+    bars.extend(builder.flush());
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::calculate_delta;
+    use crate::tape::calculate_vwap;
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    fn sample_trades() -> Vec<Trade> {
+        vec![
+            trade(dec!(100), dec!(1), Side::Buy, 0),
+            trade(dec!(102), dec!(2), Side::Sell, 30),
+            trade(dec!(99), dec!(1), Side::Buy, 59),
+            trade(dec!(101), dec!(3), Side::Unknown, 60),
+            trade(dec!(103), dec!(1), Side::Buy, 185),
+        ]
+    }
+
+    #[test]
+    fn test_time_bars() {
+        let trades = sample_trades();
+        let bars = build_bars(&trades, BarSpec::Time(60));
+
+        assert_eq!(bars.len(), 3);
+        let first = &bars[0];
+        assert_eq!((first.start, first.end), (0, 60));
+        assert_eq!(
+            (first.open, first.high, first.low, first.close),
+            (dec!(100), dec!(102), dec!(99), dec!(99))
+        );
+        assert_eq!(first.volume, dec!(4));
+        assert_eq!(first.buy_volume, dec!(2));
+        assert_eq!(first.sell_volume, dec!(2));
+        assert_eq!(first.delta, calculate_delta(&trades[..3]));
+        assert_eq!(Some(first.vwap), calculate_vwap(&trades[..3]));
+        assert_eq!(first.trade_count, 3);
+
+        assert_eq!(bars[1].delta, dec!(0));
+        assert_eq!(bars[2].start, 180);
+    }
+
+    #[test]
+    fn test_time_bars_near_max_timestamp() {
+        let trades = [
+            trade(dec!(100), dec!(1), Side::Buy, i64::MAX - 100),
+            trade(dec!(101), dec!(1), Side::Sell, i64::MAX),
+        ];
+        let bars = build_bars(&trades, BarSpec::Time(60));
+
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[1].end, i64::MAX);
+        assert!(bars.iter().all(|b| b.start < b.end));
+    }
+
+    #[test]
+    fn test_tick_and_volume_bars() {
+        let trades = sample_trades();
+
+        let ticks = build_bars(&trades, BarSpec::Tick(2));
+        assert_eq!(ticks.len(), 3);
+        assert_eq!(ticks[0].trade_count, 2);
+        assert_eq!((ticks[0].start, ticks[0].end), (0, 30));
+        assert_eq!(ticks[2].trade_count, 1);
+
+        let volume = build_bars(&trades, BarSpec::Volume(dec!(3)));
+        assert_eq!(volume.len(), 3);
+        assert_eq!(volume[0].volume, dec!(3));
+        assert_eq!(volume[1].volume, dec!(4));
+        assert_eq!(volume[1].delta, dec!(1));
+    }
+
+    #[test]
+    fn test_dollar_bars() {
+        let trades = sample_trades();
+        let bars = build_bars(&trades, BarSpec::Dollar(dec!(300)));
+
+        assert_eq!(bars[0].notional, dec!(304));
+        assert_eq!(bars[0].trade_count, 2);
+    }
+
+    #[test]
+    fn test_incremental_matches_batch() {
+        let trades = sample_trades();
+        let mut builder = BarBuilder::new(BarSpec::Time(60));
+
+        let mut bars = Vec::new();
+        for t in &trades {
+            bars.extend(builder.update(t));
+        }
+        assert_eq!(bars.len(), 2);
+        assert_eq!(builder.current().map(|b| b.trade_count), Some(1));
+        bars.extend(builder.flush());
+
+        assert_eq!(bars, build_bars(&trades, BarSpec::Time(60)));
+    }
+}
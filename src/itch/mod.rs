@@ -0,0 +1,733 @@
+//! NASDAQ TotalView-ITCH 5.0 Module
+//!
+//! This module decodes ITCH 5.0 binary messages into typed events and replays
+//! them through per-instrument [`L3Book`]s, emitting [`Trade`] records for the
+//! tape module.
+//!
+//! Prices use ITCH's 4 implied decimal places; timestamps are nanoseconds
+//! since midnight, as in the feed.
+
+use crate::orderbook::{BookError, BookSide, L3Book};
+use crate::types::{Side, Trade};
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{self, Read};
+
+/// A decoded ITCH message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ItchMessage {
+    pub stock_locate: u16,
+    pub tracking_number: u16,
+    /// Nanoseconds since midnight
+    pub timestamp: u64,
+    pub payload: ItchPayload,
+}
+
+/// Message-specific fields of an ITCH message
+#[derive(Debug, Clone, PartialEq)]
+pub enum ItchPayload {
+    /// `S` - System event (start/end of messages, market hours, ...)
+    SystemEvent { event_code: u8 },
+    /// `A` - Add order, or `F` - add order with MPID attribution
+    AddOrder {
+        order_ref: u64,
+        side: BookSide,
+        shares: u32,
+        stock: String,
+        price: Decimal,
+        mpid: Option<String>,
+    },
+    /// `E` - Order executed at its limit price
+    OrderExecuted {
+        order_ref: u64,
+        executed_shares: u32,
+        match_number: u64,
+    },
+    /// `C` - Order executed at a different price
+    OrderExecutedWithPrice {
+        order_ref: u64,
+        executed_shares: u32,
+        match_number: u64,
+        printable: bool,
+        price: Decimal,
+    },
+    /// `X` - Partial cancel
+    OrderCancel {
+        order_ref: u64,
+        cancelled_shares: u32,
+    },
+    /// `D` - Full delete
+    OrderDelete { order_ref: u64 },
+    /// `U` - Cancel/replace with a new order reference
+    OrderReplace {
+        original_order_ref: u64,
+        new_order_ref: u64,
+        shares: u32,
+        price: Decimal,
+    },
+    /// `P` - Execution against a non-displayed order
+    Trade {
+        order_ref: u64,
+        side: BookSide,
+        shares: u32,
+        stock: String,
+        price: Decimal,
+        match_number: u64,
+    },
+    /// `Q` - Opening/closing/IPO cross
+    CrossTrade {
+        shares: u64,
+        stock: String,
+        price: Decimal,
+        match_number: u64,
+        cross_type: u8,
+    },
+    /// Any other message type, skipped
+    Unsupported { message_type: u8 },
+}
+
+/// Errors raised while decoding ITCH data
+#[derive(Debug)]
+pub enum ItchError {
+    /// Zero-length message
+    Empty,
+    /// Message shorter than its type requires
+    Truncated {
+        message_type: u8,
+        expected: usize,
+        actual: usize,
+    },
+    /// Buy/sell indicator other than `B` or `S`
+    InvalidSide { byte: u8 },
+    /// Underlying reader failed
+    Io(io::Error),
+}
+
+impl fmt::Display for ItchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ItchError::Empty => write!(f, "empty ITCH message"),
+            ItchError::Truncated {
+                message_type,
+                expected,
+                actual,
+            } => write!(
+                f,
+                "truncated '{}' message: expected {expected} bytes, got {actual}",
+                char::from(*message_type)
+            ),
+            ItchError::InvalidSide { byte } => write!(f, "invalid side indicator {byte:#04x}"),
+            ItchError::Io(e) => write!(f, "I/O error: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for ItchError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ItchError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for ItchError {
+    fn from(e: io::Error) -> Self {
+        ItchError::Io(e)
+    }
+}
+
+/// Big-endian field reader over a single message
+struct Fields<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Fields<'a> {
+    fn u8(&mut self) -> u8 {
+        let v = self.buf[self.pos];
+        self.pos += 1;
+        v
+    }
+
+    fn bytes<const N: usize>(&mut self) -> [u8; N] {
+        let mut out = [0u8; N];
+        out.copy_from_slice(&self.buf[self.pos..self.pos + N]);
+        self.pos += N;
+        out
+    }
+
+    fn u16(&mut self) -> u16 {
+        u16::from_be_bytes(self.bytes())
+    }
+
+    fn u32(&mut self) -> u32 {
+        u32::from_be_bytes(self.bytes())
+    }
+
+    fn u48(&mut self) -> u64 {
+        let b: [u8; 6] = self.bytes();
+        b.iter().fold(0u64, |acc, &x| (acc << 8) | u64::from(x))
+    }
+
+    fn u64(&mut self) -> u64 {
+        u64::from_be_bytes(self.bytes())
+    }
+
+    fn price(&mut self) -> Decimal {
+        Decimal::new(i64::from(self.u32()), 4)
+    }
+
+    fn alpha<const N: usize>(&mut self) -> String {
+        let b: [u8; N] = self.bytes();
+        String::from_utf8_lossy(&b).trim_end().to_string()
+    }
+
+    fn side(&mut self) -> Result<BookSide, ItchError> {
+        match self.u8() {
+            b'B' => Ok(BookSide::Bid),
+            b'S' => Ok(BookSide::Ask),
+            byte => Err(ItchError::InvalidSide { byte }),
+        }
+    }
+}
+
+/// Total length of each supported message type, including the type byte
+fn message_length(message_type: u8) -> Option<usize> {
+    match message_type {
+        b'S' => Some(12),
+        b'A' => Some(36),
+        b'F' => Some(40),
+        b'E' => Some(31),
+        b'C' => Some(36),
+        b'X' => Some(23),
+        b'D' => Some(19),
+        b'U' => Some(35),
+        b'P' => Some(44),
+        b'Q' => Some(40),
+        _ => None,
+    }
+}
+
+/// Parse a single ITCH 5.0 message (without the 2-byte length prefix)
+///
+/// # Errors
+/// [`ItchError`] if the message is empty, truncated or malformed
+pub fn parse_message(buf: &[u8]) -> Result<ItchMessage, ItchError> {
+    let message_type = *buf.first().ok_or(ItchError::Empty)?;
+    let mut f = Fields { buf, pos: 1 };
+    // Every message type starts with the same 11-byte header
+    let (stock_locate, tracking_number, timestamp) = if buf.len() >= 11 {
+        (f.u16(), f.u16(), f.u48())
+    } else {
+        (0, 0, 0)
+    };
+
+    let Some(expected) = message_length(message_type) else {
+        return Ok(ItchMessage {
+            stock_locate,
+            tracking_number,
+            timestamp,
+            payload: ItchPayload::Unsupported { message_type },
+        });
+    };
+    if buf.len() < expected {
+        return Err(ItchError::Truncated {
+            message_type,
+            expected,
+            actual: buf.len(),
+        });
+    }
+
+    let payload = match message_type {
+        b'S' => ItchPayload::SystemEvent { event_code: f.u8() },
+        b'A' | b'F' => ItchPayload::AddOrder {
+            order_ref: f.u64(),
+            side: f.side()?,
+            shares: f.u32(),
+            stock: f.alpha::<8>(),
+            price: f.price(),
+            mpid: (message_type == b'F').then(|| f.alpha::<4>()),
+        },
+        b'E' => ItchPayload::OrderExecuted {
+            order_ref: f.u64(),
+            executed_shares: f.u32(),
+            match_number: f.u64(),
+        },
+        b'C' => ItchPayload::OrderExecutedWithPrice {
+            order_ref: f.u64(),
+            executed_shares: f.u32(),
+            match_number: f.u64(),
+            printable: f.u8() == b'Y',
+            price: f.price(),
+        },
+        b'X' => ItchPayload::OrderCancel {
+            order_ref: f.u64(),
+            cancelled_shares: f.u32(),
+        },
+        b'D' => ItchPayload::OrderDelete { order_ref: f.u64() },
+        b'U' => ItchPayload::OrderReplace {
+            original_order_ref: f.u64(),
+            new_order_ref: f.u64(),
+            shares: f.u32(),
+            price: f.price(),
+        },
+        b'P' => ItchPayload::Trade {
+            order_ref: f.u64(),
+            side: f.side()?,
+            shares: f.u32(),
+            stock: f.alpha::<8>(),
+            price: f.price(),
+            match_number: f.u64(),
+        },
+        b'Q' => ItchPayload::CrossTrade {
+            shares: f.u64(),
+            stock: f.alpha::<8>(),
+            price: f.price(),
+            match_number: f.u64(),
+            cross_type: f.u8(),
+        },
+        _ => unreachable!("message_length covers every decoded type"),
+    };
+
+    Ok(ItchMessage {
+        stock_locate,
+        tracking_number,
+        timestamp,
+        payload,
+    })
+}
+
+/// Iterator over length-prefixed ITCH messages (NASDAQ BinaryFILE framing)
+pub struct ItchReader<R: Read> {
+    reader: R,
+    buf: Vec<u8>,
+}
+
+impl<R: Read> ItchReader<R> {
+    /// Wrap a reader positioned at the start of a message
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            buf: Vec::new(),
+        }
+    }
+
+    fn read_next(&mut self) -> Result<Option<ItchMessage>, ItchError> {
+        // Only EOF before the first prefix byte is a clean end of stream
+        let mut len = [0u8; 2];
+        loop {
+            match self.reader.read(&mut len[..1]) {
+                Ok(0) => return Ok(None),
+                Ok(_) => break,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        self.reader.read_exact(&mut len[1..])?;
+
+        self.buf.resize(usize::from(u16::from_be_bytes(len)), 0);
+        self.reader.read_exact(&mut self.buf)?;
+        parse_message(&self.buf).map(Some)
+    }
+}
+
+impl<R: Read> Iterator for ItchReader<R> {
+    type Item = Result<ItchMessage, ItchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_next().transpose()
+    }
+}
+
+/// Replays ITCH messages into one [`L3Book`] per stock locate code
+#[derive(Debug, Clone, Default)]
+pub struct ItchReplay {
+    books: HashMap<u16, L3Book>,
+    symbols: HashMap<String, u16>,
+}
+
+impl ItchReplay {
+    /// Create an empty replay
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Book for an order-bearing message, which needs an earlier add
+    fn book_mut(&mut self, stock_locate: u16, order_id: u64) -> Result<&mut L3Book, BookError> {
+        self.books
+            .get_mut(&stock_locate)
+            .ok_or(BookError::UnknownOrder { order_id })
+    }
+
+    /// Apply a message to its instrument's book
+    ///
+    /// Books are created by the first add order of a stock locate; system
+    /// events and unsupported messages leave the books untouched.
+    ///
+    /// # Returns
+    /// A [`Trade`] for printable executions, non-displayed trades and
+    /// crosses. Crosses have no aggressor and are reported as
+    /// [`Side::Unknown`]. Trade timestamps are ITCH's nanoseconds since
+    /// midnight, not the seconds the loader produces by default.
+    ///
+    /// # Errors
+    /// [`BookError`] if the message references an unknown order or is
+    /// inconsistent with the book
+    pub fn process(&mut self, msg: &ItchMessage) -> Result<Option<Trade>, BookError> {
+        let ts = i64::try_from(msg.timestamp).unwrap_or(i64::MAX);
+
+        match &msg.payload {
+            ItchPayload::AddOrder {
+                order_ref,
+                side,
+                shares,
+                stock,
+                price,
+                ..
+            } => {
+                self.symbols
+                    .entry(stock.clone())
+                    .or_insert(msg.stock_locate);
+                let book = self.books.entry(msg.stock_locate).or_default();
+                book.add_order(*order_ref, *side, *price, Decimal::from(*shares), ts)?;
+                Ok(None)
+            }
+            ItchPayload::OrderExecuted {
+                order_ref,
+                executed_shares,
+                ..
+            } => {
+                let book = self.book_mut(msg.stock_locate, *order_ref)?;
+                let exec =
+                    book.execute_order(*order_ref, Decimal::from(*executed_shares), None, ts)?;
+                Ok(Some(exec.to_trade()))
+            }
+            ItchPayload::OrderExecutedWithPrice {
+                order_ref,
+                executed_shares,
+                printable,
+                price,
+                ..
+            } => {
+                let book = self.book_mut(msg.stock_locate, *order_ref)?;
+                let exec = book.execute_order(
+                    *order_ref,
+                    Decimal::from(*executed_shares),
+                    Some(*price),
+                    ts,
+                )?;
+                Ok(printable.then(|| exec.to_trade()))
+            }
+            ItchPayload::OrderCancel {
+                order_ref,
+                cancelled_shares,
+            } => {
+                let book = self.book_mut(msg.stock_locate, *order_ref)?;
+                book.cancel_order(*order_ref, Some(Decimal::from(*cancelled_shares)), ts)?;
+                Ok(None)
+            }
+            ItchPayload::OrderDelete { order_ref } => {
+                let book = self.book_mut(msg.stock_locate, *order_ref)?;
+                book.cancel_order(*order_ref, None, ts)?;
+                Ok(None)
+            }
+            ItchPayload::OrderReplace {
+                original_order_ref,
+                new_order_ref,
+                shares,
+                price,
+            } => {
+                let book = self.book_mut(msg.stock_locate, *original_order_ref)?;
+                let side = book.order(*original_order_ref).map(|o| o.side).ok_or(
+                    BookError::UnknownOrder {
+                        order_id: *original_order_ref,
+                    },
+                )?;
+                // Validate the new order first so a failed replace leaves
+                // the original resting
+                if new_order_ref != original_order_ref && book.order(*new_order_ref).is_some() {
+                    return Err(BookError::DuplicateOrder {
+                        order_id: *new_order_ref,
+                    });
+                }
+                if *shares == 0 {
+                    return Err(BookError::InvalidQuantity {
+                        order_id: *new_order_ref,
+                        quantity: Decimal::ZERO,
+                    });
+                }
+                book.cancel_order(*original_order_ref, None, ts)?;
+                book.add_order(*new_order_ref, side, *price, Decimal::from(*shares), ts)?;
+                Ok(None)
+            }
+            ItchPayload::Trade {
+                side,
+                shares,
+                stock,
+                price,
+                ..
+            } => {
+                self.symbols
+                    .entry(stock.clone())
+                    .or_insert(msg.stock_locate);
+                Ok(Some(Trade {
+                    price: *price,
+                    quantity: Decimal::from(*shares),
+                    side: match side {
+                        BookSide::Bid => Side::Sell,
+                        BookSide::Ask => Side::Buy,
+                    },
+                    timestamp: ts,
+                }))
+            }
+            ItchPayload::CrossTrade {
+                shares,
+                stock,
+                price,
+                ..
+            } => {
+                self.symbols
+                    .entry(stock.clone())
+                    .or_insert(msg.stock_locate);
+                Ok(Some(Trade {
+                    price: *price,
+                    quantity: Decimal::from(*shares),
+                    side: Side::Unknown,
+                    timestamp: ts,
+                }))
+            }
+            ItchPayload::SystemEvent { .. } | ItchPayload::Unsupported { .. } => Ok(None),
+        }
+    }
+
+    /// Book for a stock locate code
+    #[must_use]
+    pub fn book(&self, stock_locate: u16) -> Option<&L3Book> {
+        self.books.get(&stock_locate)
+    }
+
+    /// Book for a symbol seen in an add, trade or cross message
+    #[must_use]
+    pub fn book_for_symbol(&self, symbol: &str) -> Option<&L3Book> {
+        self.symbols
+            .get(symbol)
+            .and_then(|locate| self.books.get(locate))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn header(message_type: u8, locate: u16, timestamp: u64) -> Vec<u8> {
+        let mut buf = vec![message_type];
+        buf.extend_from_slice(&locate.to_be_bytes());
+        buf.extend_from_slice(&0u16.to_be_bytes());
+        buf.extend_from_slice(&timestamp.to_be_bytes()[2..]);
+        buf
+    }
+
+    fn add_order(order_ref: u64, side: u8, shares: u32, price: u32, mpid: bool) -> Vec<u8> {
+        let mut buf = header(if mpid { b'F' } else { b'A' }, 1, 1_000);
+        buf.extend_from_slice(&order_ref.to_be_bytes());
+        buf.push(side);
+        buf.extend_from_slice(&shares.to_be_bytes());
+        buf.extend_from_slice(b"AAPL    ");
+        buf.extend_from_slice(&price.to_be_bytes());
+        if mpid {
+            buf.extend_from_slice(b"GSCO");
+        }
+        buf
+    }
+
+    fn executed(order_ref: u64, shares: u32) -> Vec<u8> {
+        let mut buf = header(b'E', 1, 2_000);
+        buf.extend_from_slice(&order_ref.to_be_bytes());
+        buf.extend_from_slice(&shares.to_be_bytes());
+        buf.extend_from_slice(&77u64.to_be_bytes());
+        buf
+    }
+
+    fn replace(original: u64, new: u64, shares: u32, price: u32) -> Vec<u8> {
+        let mut buf = header(b'U', 1, 3_000);
+        buf.extend_from_slice(&original.to_be_bytes());
+        buf.extend_from_slice(&new.to_be_bytes());
+        buf.extend_from_slice(&shares.to_be_bytes());
+        buf.extend_from_slice(&price.to_be_bytes());
+        buf
+    }
+
+    fn cross(shares: u64, price: u32) -> Vec<u8> {
+        let mut buf = header(b'Q', 1, 4_000);
+        buf.extend_from_slice(&shares.to_be_bytes());
+        buf.extend_from_slice(b"AAPL    ");
+        buf.extend_from_slice(&price.to_be_bytes());
+        buf.extend_from_slice(&99u64.to_be_bytes());
+        buf.push(b'O');
+        buf
+    }
+
+    fn framed(messages: &[Vec<u8>]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for m in messages {
+            out.extend_from_slice(&u16::try_from(m.len()).unwrap().to_be_bytes());
+            out.extend_from_slice(m);
+        }
+        out
+    }
+
+    #[test]
+    fn test_parse_add_order() {
+        let msg = parse_message(&add_order(42, b'B', 100, 1_505_000, true)).unwrap();
+
+        assert_eq!(msg.stock_locate, 1);
+        assert_eq!(msg.timestamp, 1_000);
+        assert_eq!(
+            msg.payload,
+            ItchPayload::AddOrder {
+                order_ref: 42,
+                side: BookSide::Bid,
+                shares: 100,
+                stock: "AAPL".to_string(),
+                price: dec!(150.5),
+                mpid: Some("GSCO".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(matches!(parse_message(&[]), Err(ItchError::Empty)));
+
+        let short = &executed(1, 1)[..20];
+        assert!(matches!(
+            parse_message(short),
+            Err(ItchError::Truncated {
+                message_type: b'E',
+                expected: 31,
+                actual: 20
+            })
+        ));
+
+        assert!(matches!(
+            parse_message(&add_order(1, b'Z', 1, 1, false)),
+            Err(ItchError::InvalidSide { byte: b'Z' })
+        ));
+
+        let other = parse_message(b"R...").unwrap();
+        assert_eq!(
+            other.payload,
+            ItchPayload::Unsupported { message_type: b'R' }
+        );
+        assert_eq!(other.timestamp, 0);
+
+        // The header of unsupported messages is still parsed
+        let mut directory = header(b'R', 7, 5_000);
+        directory.extend_from_slice(b"AAPL    ");
+        let other = parse_message(&directory).unwrap();
+        assert_eq!(
+            other.payload,
+            ItchPayload::Unsupported { message_type: b'R' }
+        );
+        assert_eq!((other.stock_locate, other.timestamp), (7, 5_000));
+    }
+
+    #[test]
+    fn test_reader_replays_into_book() {
+        let data = framed(&[
+            add_order(1, b'B', 100, 1_000_000, false),
+            add_order(2, b'S', 50, 1_010_000, false),
+            executed(2, 20),
+            replace(1, 3, 80, 1_005_000),
+            cross(500, 1_002_500),
+        ]);
+
+        let mut replay = ItchReplay::new();
+        let mut trades = Vec::new();
+        for msg in ItchReader::new(data.as_slice()) {
+            if let Some(trade) = replay.process(&msg.unwrap()).unwrap() {
+                trades.push(trade);
+            }
+        }
+
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].side, Side::Buy);
+        assert_eq!(trades[0].price, dec!(101));
+        assert_eq!(trades[0].quantity, dec!(20));
+        assert_eq!(trades[1].side, Side::Unknown);
+        assert_eq!(trades[1].quantity, dec!(500));
+
+        let book = replay.book_for_symbol("AAPL").unwrap();
+        let view = book.to_orderbook(None);
+        assert_eq!(view.bids.len(), 1);
+        assert_eq!(view.bids[0].price, dec!(100.5));
+        assert_eq!(view.bids[0].quantity, dec!(80));
+        assert_eq!(view.asks[0].quantity, dec!(30));
+        assert!(book.order(1).is_none());
+    }
+
+    #[test]
+    fn test_reader_reports_truncated_length_prefix() {
+        let mut data = framed(&[add_order(1, b'B', 100, 1_000_000, false)]);
+        data.push(0);
+
+        let results: Vec<_> = ItchReader::new(data.as_slice()).collect();
+
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert!(matches!(
+            &results[1],
+            Err(ItchError::Io(e)) if e.kind() == io::ErrorKind::UnexpectedEof
+        ));
+    }
+
+    #[test]
+    fn test_replace_to_duplicate_keeps_original() {
+        let data = framed(&[
+            add_order(1, b'B', 100, 1_000_000, false),
+            add_order(2, b'B', 50, 990_000, false),
+            replace(1, 2, 80, 1_005_000),
+        ]);
+
+        let mut replay = ItchReplay::new();
+        let results: Vec<_> = ItchReader::new(data.as_slice())
+            .map(|msg| replay.process(&msg.unwrap()))
+            .collect();
+
+        assert!(matches!(
+            results[2],
+            Err(BookError::DuplicateOrder { order_id: 2 })
+        ));
+        let book = replay.book_for_symbol("AAPL").unwrap();
+        assert_eq!(book.order(1).unwrap().quantity, dec!(100));
+        assert_eq!(book.order(2).unwrap().quantity, dec!(50));
+    }
+
+    #[test]
+    fn test_replay_unknown_order() {
+        let mut replay = ItchReplay::new();
+        let msg = parse_message(&executed(9, 1)).unwrap();
+
+        assert!(matches!(
+            replay.process(&msg),
+            Err(BookError::UnknownOrder { order_id: 9 })
+        ));
+        assert!(replay.book(1).is_none());
+
+        // Messages that carry no orders do not create books
+        let mut event = header(b'S', 7, 0);
+        event.push(b'O');
+        assert!(matches!(
+            replay.process(&parse_message(&event).unwrap()),
+            Ok(None)
+        ));
+        let other = parse_message(b"R...").unwrap();
+        assert!(matches!(replay.process(&other), Ok(None)));
+        assert!(replay.book(7).is_none());
+        assert!(replay.book(0).is_none());
+    }
+}
@@ -1,9 +1,13 @@
 //! Market Microstructure Analytics Engine
+pub mod bars;
+pub mod itch;
+pub mod loader;
 pub mod metrics;
 pub mod orderbook;
 pub mod patterns;
+mod stats;
 pub mod tape;
 pub mod types;
 pub mod visualization;
 
-pub use types::{Level, OrderBook, Trade};
+pub use types::{AccountedTrade, Level, OrderBook, ParseSideError, Side, Trade};
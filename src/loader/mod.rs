@@ -0,0 +1,695 @@
+//! Data Loading Module
+//!
+//! This module reads trades and order book snapshots from CSV and
+//! newline-delimited JSON files, with configurable column names and
+//! timestamp units, so captured data can be fed to the analytics modules.
+//!
+//! Book snapshots in CSV use one row per snapshot with numbered level
+//! columns (`bid_price_1`, `bid_qty_1`, `ask_price_1`, ...). In JSON lines,
+//! levels may be objects (`{"price": .., "quantity": ..}`) or
+//! `[price, quantity]` pairs.
+
+use crate::types::{AccountedTrade, Level, OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use serde_json::Value;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+/// Unit of a timestamp column
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TimestampUnit {
+    #[default]
+    Seconds,
+    Millis,
+    Micros,
+    Nanos,
+}
+
+impl TimestampUnit {
+    fn nanos(self) -> i128 {
+        match self {
+            TimestampUnit::Seconds => 1_000_000_000,
+            TimestampUnit::Millis => 1_000_000,
+            TimestampUnit::Micros => 1_000,
+            TimestampUnit::Nanos => 1,
+        }
+    }
+}
+
+impl FromStr for TimestampUnit {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "s" | "sec" | "seconds" => Ok(TimestampUnit::Seconds),
+            "ms" | "millis" | "milliseconds" => Ok(TimestampUnit::Millis),
+            "us" | "micros" | "microseconds" => Ok(TimestampUnit::Micros),
+            "ns" | "nanos" | "nanoseconds" => Ok(TimestampUnit::Nanos),
+            other => Err(format!("unknown timestamp unit '{other}'")),
+        }
+    }
+}
+
+/// Column (or JSON key) names for trade records
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TradeColumns {
+    pub price: String,
+    pub quantity: String,
+    /// Aggressor side; trades get [`Side::Unknown`] when `None` or absent
+    pub side: Option<String>,
+    pub timestamp: String,
+    /// Buyer and seller account ids; left empty when `None` or absent
+    pub buyer_id: Option<String>,
+    pub seller_id: Option<String>,
+}
+
+impl Default for TradeColumns {
+    fn default() -> Self {
+        Self {
+            price: "price".to_string(),
+            quantity: "quantity".to_string(),
+            side: Some("side".to_string()),
+            timestamp: "timestamp".to_string(),
+            buyer_id: Some("buyer_id".to_string()),
+            seller_id: Some("seller_id".to_string()),
+        }
+    }
+}
+
+/// Column (or JSON key) names for order book snapshots
+///
+/// CSV level columns are templates where `{n}` is replaced by the 1-based
+/// level number.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BookColumns {
+    pub timestamp: String,
+    pub bid_price: String,
+    pub bid_quantity: String,
+    pub ask_price: String,
+    pub ask_quantity: String,
+    /// JSON key holding the bid levels
+    pub bids: String,
+    /// JSON key holding the ask levels
+    pub asks: String,
+}
+
+impl Default for BookColumns {
+    fn default() -> Self {
+        Self {
+            timestamp: "timestamp".to_string(),
+            bid_price: "bid_price_{n}".to_string(),
+            bid_quantity: "bid_qty_{n}".to_string(),
+            ask_price: "ask_price_{n}".to_string(),
+            ask_quantity: "ask_qty_{n}".to_string(),
+            bids: "bids".to_string(),
+            asks: "asks".to_string(),
+        }
+    }
+}
+
+/// Loader configuration
+#[derive(Debug, Clone, Default)]
+pub struct LoaderConfig {
+    pub trade_columns: TradeColumns,
+    pub book_columns: BookColumns,
+    /// Unit of timestamps in the input
+    pub timestamp_unit: TimestampUnit,
+    /// Unit of timestamps in the loaded records; `None` keeps the input
+    /// unit, so sub-second timestamps are not truncated
+    pub output_unit: Option<TimestampUnit>,
+    /// CSV field delimiter (`None` for comma)
+    pub delimiter: Option<u8>,
+}
+
+impl LoaderConfig {
+    /// Unit of timestamps in the loaded records
+    #[must_use]
+    pub fn output_unit(&self) -> TimestampUnit {
+        self.output_unit.unwrap_or(self.timestamp_unit)
+    }
+}
+
+/// Errors raised while loading data
+#[derive(Debug)]
+pub enum LoadError {
+    /// Underlying reader failed
+    Io(io::Error),
+    /// A required column is not in the CSV header
+    MissingColumn { column: String },
+    /// A record could not be parsed (1-based line number)
+    Parse { line: u64, message: String },
+    /// File extension does not identify a supported format
+    UnsupportedFormat { path: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LoadError::Io(e) => write!(f, "I/O error: {e}"),
+            LoadError::MissingColumn { column } => write!(f, "missing column '{column}'"),
+            LoadError::Parse { line, message } => write!(f, "line {line}: {message}"),
+            LoadError::UnsupportedFormat { path } => {
+                write!(f, "unsupported file format: {path}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            LoadError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(e: io::Error) -> Self {
+        LoadError::Io(e)
+    }
+}
+
+fn parse_error(line: u64, message: impl Into<String>) -> LoadError {
+    LoadError::Parse {
+        line,
+        message: message.into(),
+    }
+}
+
+fn parse_decimal(raw: &str, field: &str, line: u64) -> Result<Decimal, LoadError> {
+    let raw = raw.trim();
+    Decimal::from_str(raw)
+        .or_else(|_| Decimal::from_scientific(raw))
+        .map_err(|_| parse_error(line, format!("invalid {field} '{raw}'")))
+}
+
+fn parse_timestamp(raw: &str, config: &LoaderConfig, line: u64) -> Result<i64, LoadError> {
+    let out_of_range = || parse_error(line, format!("timestamp '{raw}' out of range"));
+    let value = parse_decimal(raw, "timestamp", line)?;
+    let scaled = value
+        .checked_mul(Decimal::from(config.timestamp_unit.nanos()))
+        .and_then(|v| v.checked_div(Decimal::from(config.output_unit().nanos())))
+        .ok_or_else(out_of_range)?;
+    i64::try_from(scaled.trunc()).map_err(|_| out_of_range())
+}
+
+/// Render a JSON scalar as the string a CSV cell would hold
+fn json_scalar(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) => Some(s.clone()),
+        Value::Number(n) => Some(n.to_string()),
+        Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+fn build_trade(
+    get: impl Fn(&str) -> Option<String>,
+    config: &LoaderConfig,
+    line: u64,
+) -> Result<AccountedTrade, LoadError> {
+    let cols = &config.trade_columns;
+    let field =
+        |name: &str| get(name).ok_or_else(|| parse_error(line, format!("missing '{name}'")));
+
+    let trade = Trade {
+        price: parse_decimal(&field(&cols.price)?, "price", line)?,
+        quantity: parse_decimal(&field(&cols.quantity)?, "quantity", line)?,
+        side: cols
+            .side
+            .as_deref()
+            .and_then(&get)
+            .map(|s| s.parse::<Side>())
+            .transpose()
+            .map_err(|e| parse_error(line, e.to_string()))?
+            .unwrap_or_default(),
+        timestamp: parse_timestamp(&field(&cols.timestamp)?, config, line)?,
+    };
+    Ok(AccountedTrade {
+        trade,
+        buyer_id: cols.buyer_id.as_deref().and_then(&get),
+        seller_id: cols.seller_id.as_deref().and_then(&get),
+    })
+}
+
+fn without_accounts(trades: Vec<AccountedTrade>) -> Vec<Trade> {
+    trades.into_iter().map(|t| t.trade).collect()
+}
+
+fn csv_reader<R: Read>(reader: R, config: &LoaderConfig) -> csv::Reader<R> {
+    csv::ReaderBuilder::new()
+        .delimiter(config.delimiter.unwrap_or(b','))
+        .trim(csv::Trim::All)
+        .from_reader(reader)
+}
+
+fn csv_error(e: &csv::Error) -> LoadError {
+    let line = e.position().map_or(0, csv::Position::line);
+    parse_error(line, e.to_string())
+}
+
+fn column_index(headers: &csv::StringRecord, column: &str) -> Option<usize> {
+    headers.iter().position(|h| h == column)
+}
+
+/// Read trades from CSV with a header row
+///
+/// # Errors
+/// [`LoadError`] on I/O failure, missing columns or unparsable rows
+pub fn read_trades_csv<R: Read>(reader: R, config: &LoaderConfig) -> Result<Vec<Trade>, LoadError> {
+    read_accounted_trades_csv(reader, config).map(without_accounts)
+}
+
+/// Read trades with buyer/seller account ids from CSV with a header row
+///
+/// # Errors
+/// [`LoadError`] on I/O failure, missing columns or unparsable rows
+pub fn read_accounted_trades_csv<R: Read>(
+    reader: R,
+    config: &LoaderConfig,
+) -> Result<Vec<AccountedTrade>, LoadError> {
+    let mut rdr = csv_reader(reader, config);
+    let headers = rdr.headers().map_err(|e| csv_error(&e))?.clone();
+    let cols = &config.trade_columns;
+
+    for column in [&cols.price, &cols.quantity, &cols.timestamp] {
+        if column_index(&headers, column).is_none() {
+            return Err(LoadError::MissingColumn {
+                column: column.clone(),
+            });
+        }
+    }
+
+    let mut trades = Vec::new();
+    for record in rdr.records() {
+        let record = record.map_err(|e| csv_error(&e))?;
+        let line = record.position().map_or(0, csv::Position::line);
+        let get = |name: &str| {
+            column_index(&headers, name)
+                .and_then(|i| record.get(i))
+                .filter(|v| !v.is_empty())
+                .map(str::to_string)
+        };
+        trades.push(build_trade(get, config, line)?);
+    }
+
+    Ok(trades)
+}
+
+/// Read trades from newline-delimited JSON (blank lines are skipped)
+///
+/// # Errors
+/// [`LoadError`] on I/O failure or unparsable lines
+pub fn read_trades_jsonl<R: BufRead>(
+    reader: R,
+    config: &LoaderConfig,
+) -> Result<Vec<Trade>, LoadError> {
+    read_accounted_trades_jsonl(reader, config).map(without_accounts)
+}
+
+/// Read trades with buyer/seller account ids from newline-delimited JSON
+///
+/// # Errors
+/// [`LoadError`] on I/O failure or unparsable lines
+pub fn read_accounted_trades_jsonl<R: BufRead>(
+    reader: R,
+    config: &LoaderConfig,
+) -> Result<Vec<AccountedTrade>, LoadError> {
+    let mut trades = Vec::new();
+    for (value, line) in json_lines(reader)? {
+        let get = |name: &str| value.get(name).and_then(json_scalar);
+        trades.push(build_trade(get, config, line)?);
+    }
+    Ok(trades)
+}
+
+fn json_lines<R: BufRead>(reader: R) -> Result<Vec<(Value, u64)>, LoadError> {
+    let mut values = Vec::new();
+    for (i, text) in reader.lines().enumerate() {
+        let text = text?;
+        let line = i as u64 + 1;
+        if text.trim().is_empty() {
+            continue;
+        }
+        let value: Value =
+            serde_json::from_str(&text).map_err(|e| parse_error(line, e.to_string()))?;
+        if !value.is_object() {
+            return Err(parse_error(line, "expected a JSON object"));
+        }
+        values.push((value, line));
+    }
+    Ok(values)
+}
+
+/// Level column indices for one side, in level order
+fn level_columns(
+    headers: &csv::StringRecord,
+    price_template: &str,
+    quantity_template: &str,
+) -> Vec<(usize, usize)> {
+    let mut columns = Vec::new();
+    for n in 1.. {
+        let price = price_template.replace("{n}", &n.to_string());
+        let quantity = quantity_template.replace("{n}", &n.to_string());
+        match (
+            column_index(headers, &price),
+            column_index(headers, &quantity),
+        ) {
+            (Some(p), Some(q)) => columns.push((p, q)),
+            _ => break,
+        }
+    }
+    columns
+}
+
+fn csv_levels(
+    record: &csv::StringRecord,
+    columns: &[(usize, usize)],
+    line: u64,
+) -> Result<Vec<Level>, LoadError> {
+    let mut levels = Vec::new();
+    for &(p, q) in columns {
+        let (price, quantity) = (record.get(p).unwrap_or(""), record.get(q).unwrap_or(""));
+        if price.is_empty() || quantity.is_empty() {
+            continue;
+        }
+        levels.push(Level {
+            price: parse_decimal(price, "price", line)?,
+            quantity: parse_decimal(quantity, "quantity", line)?,
+        });
+    }
+    Ok(levels)
+}
+
+/// Read order book snapshots from CSV, one snapshot per row
+///
+/// # Errors
+/// [`LoadError`] on I/O failure, missing columns or unparsable rows
+pub fn read_books_csv<R: Read>(
+    reader: R,
+    config: &LoaderConfig,
+) -> Result<Vec<OrderBook>, LoadError> {
+    let mut rdr = csv_reader(reader, config);
+    let headers = rdr.headers().map_err(|e| csv_error(&e))?.clone();
+    let cols = &config.book_columns;
+
+    let ts_index =
+        column_index(&headers, &cols.timestamp).ok_or_else(|| LoadError::MissingColumn {
+            column: cols.timestamp.clone(),
+        })?;
+    let bid_columns = level_columns(&headers, &cols.bid_price, &cols.bid_quantity);
+    let ask_columns = level_columns(&headers, &cols.ask_price, &cols.ask_quantity);
+    if bid_columns.is_empty() && ask_columns.is_empty() {
+        return Err(LoadError::MissingColumn {
+            column: cols.bid_price.replace("{n}", "1"),
+        });
+    }
+
+    let mut books = Vec::new();
+    for record in rdr.records() {
+        let record = record.map_err(|e| csv_error(&e))?;
+        let line = record.position().map_or(0, csv::Position::line);
+        books.push(OrderBook {
+            bids: csv_levels(&record, &bid_columns, line)?,
+            asks: csv_levels(&record, &ask_columns, line)?,
+            timestamp: parse_timestamp(record.get(ts_index).unwrap_or(""), config, line)?,
+        });
+    }
+
+    Ok(books)
+}
+
+fn json_levels(value: Option<&Value>, line: u64) -> Result<Vec<Level>, LoadError> {
+    let Some(value) = value else {
+        return Ok(Vec::new());
+    };
+    let entries = value
+        .as_array()
+        .ok_or_else(|| parse_error(line, "levels must be an array"))?;
+
+    entries
+        .iter()
+        .map(|entry| {
+            let (price, quantity) = match entry {
+                Value::Array(pair) if pair.len() >= 2 => {
+                    (json_scalar(&pair[0]), json_scalar(&pair[1]))
+                }
+                Value::Object(_) => (
+                    entry.get("price").and_then(json_scalar),
+                    entry.get("quantity").and_then(json_scalar),
+                ),
+                _ => (None, None),
+            };
+            match (price, quantity) {
+                (Some(p), Some(q)) => Ok(Level {
+                    price: parse_decimal(&p, "price", line)?,
+                    quantity: parse_decimal(&q, "quantity", line)?,
+                }),
+                _ => Err(parse_error(line, "malformed level")),
+            }
+        })
+        .collect()
+}
+
+/// Read order book snapshots from newline-delimited JSON
+///
+/// # Errors
+/// [`LoadError`] on I/O failure or unparsable lines
+pub fn read_books_jsonl<R: BufRead>(
+    reader: R,
+    config: &LoaderConfig,
+) -> Result<Vec<OrderBook>, LoadError> {
+    let cols = &config.book_columns;
+    let mut books = Vec::new();
+    for (value, line) in json_lines(reader)? {
+        let timestamp = value
+            .get(&cols.timestamp)
+            .and_then(json_scalar)
+            .ok_or_else(|| parse_error(line, format!("missing '{}'", cols.timestamp)))?;
+        books.push(OrderBook {
+            bids: json_levels(value.get(&cols.bids), line)?,
+            asks: json_levels(value.get(&cols.asks), line)?,
+            timestamp: parse_timestamp(&timestamp, config, line)?,
+        });
+    }
+    Ok(books)
+}
+
+/// Supported input formats
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    Csv,
+    JsonLines,
+}
+
+impl Format {
+    /// Guess the format from a file extension (`.csv`, `.jsonl`, `.ndjson`, `.json`)
+    #[must_use]
+    pub fn from_path(path: &Path) -> Option<Format> {
+        match path.extension()?.to_str()?.to_ascii_lowercase().as_str() {
+            "csv" => Some(Format::Csv),
+            "jsonl" | "ndjson" | "json" => Some(Format::JsonLines),
+            _ => None,
+        }
+    }
+}
+
+fn open(path: &Path) -> Result<(Format, BufReader<File>), LoadError> {
+    let format = Format::from_path(path).ok_or_else(|| LoadError::UnsupportedFormat {
+        path: path.display().to_string(),
+    })?;
+    Ok((format, BufReader::new(File::open(path)?)))
+}
+
+/// Load trades from a file, choosing the format by extension
+///
+/// # Errors
+/// [`LoadError`] on unsupported extension, I/O failure or bad records
+pub fn load_trades(path: impl AsRef<Path>, config: &LoaderConfig) -> Result<Vec<Trade>, LoadError> {
+    load_accounted_trades(path, config).map(without_accounts)
+}
+
+/// Load trades with buyer/seller account ids, choosing the format by
+/// extension
+///
+/// # Errors
+/// [`LoadError`] on unsupported extension, I/O failure or bad records
+pub fn load_accounted_trades(
+    path: impl AsRef<Path>,
+    config: &LoaderConfig,
+) -> Result<Vec<AccountedTrade>, LoadError> {
+    match open(path.as_ref())? {
+        (Format::Csv, reader) => read_accounted_trades_csv(reader, config),
+        (Format::JsonLines, reader) => read_accounted_trades_jsonl(reader, config),
+    }
+}
+
+/// Load order book snapshots from a file, choosing the format by extension
+///
+/// # Errors
+/// [`LoadError`] on unsupported extension, I/O failure or bad records
+pub fn load_books(
+    path: impl AsRef<Path>,
+    config: &LoaderConfig,
+) -> Result<Vec<OrderBook>, LoadError> {
+    match open(path.as_ref())? {
+        (Format::Csv, reader) => read_books_csv(reader, config),
+        (Format::JsonLines, reader) => read_books_jsonl(reader, config),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_read_trades_csv() {
+        let data = "timestamp,price,quantity,side\n\
+                    1696435200,50000.5,1.2,B\n\
+                    1696435201,50001,0.5,SELL\n\
+                    1696435202,50002,2,\n";
+
+        let trades = read_trades_csv(data.as_bytes(), &LoaderConfig::default()).unwrap();
+
+        assert_eq!(trades.len(), 3);
+        assert_eq!(trades[0].price, dec!(50000.5));
+        assert_eq!(trades[0].side, Side::Buy);
+        assert_eq!(trades[1].side, Side::Sell);
+        assert_eq!(trades[2].side, Side::Unknown);
+        assert_eq!(trades[2].timestamp, 1696435202);
+    }
+
+    #[test]
+    fn test_invalid_side_is_an_error() {
+        let data = "timestamp,price,quantity,side\n1,100,1,B\n2,100,1,byu\n";
+        let err = read_trades_csv(data.as_bytes(), &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 3, .. }));
+    }
+
+    #[test]
+    fn test_column_mapping_and_units() {
+        let data = "ts;px;qty\n1696435200123;100;1\n";
+        let config = LoaderConfig {
+            trade_columns: TradeColumns {
+                price: "px".to_string(),
+                quantity: "qty".to_string(),
+                side: None,
+                timestamp: "ts".to_string(),
+                buyer_id: None,
+                seller_id: None,
+            },
+            timestamp_unit: TimestampUnit::Millis,
+            output_unit: Some(TimestampUnit::Micros),
+            delimiter: Some(b';'),
+            ..LoaderConfig::default()
+        };
+
+        let trades = read_trades_csv(data.as_bytes(), &config).unwrap();
+        assert_eq!(trades[0].timestamp, 1_696_435_200_123_000);
+        assert_eq!(trades[0].side, Side::Unknown);
+
+        // Without an output unit, timestamps keep the input precision
+        let config = LoaderConfig {
+            output_unit: None,
+            ..config
+        };
+        let trades = read_trades_csv(data.as_bytes(), &config).unwrap();
+        assert_eq!(trades[0].timestamp, 1_696_435_200_123);
+    }
+
+    #[test]
+    fn test_csv_errors_carry_line_numbers() {
+        let data = "timestamp,price,quantity\n1,100,1\n2,abc,1\n";
+        let err = read_trades_csv(data.as_bytes(), &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 3, .. }));
+
+        let err =
+            read_trades_csv("timestamp,price\n".as_bytes(), &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err, LoadError::MissingColumn { column } if column == "quantity"));
+    }
+
+    #[test]
+    fn test_read_trades_jsonl() {
+        let data = r#"{"price": 100.5, "quantity": "2", "side": "ask", "timestamp": 10, "buyer_id": "A1", "seller_id": 7}
+
+{"price": "101", "quantity": 1, "timestamp": 11}
+{"price": "x", "quantity": 1, "timestamp": 12}
+"#;
+        let err = read_trades_jsonl(data.as_bytes(), &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 4, .. }));
+
+        let data: String = data.lines().take(3).collect::<Vec<_>>().join("\n");
+        let trades = read_trades_jsonl(data.as_bytes(), &LoaderConfig::default()).unwrap();
+        assert_eq!(trades.len(), 2);
+        assert_eq!(trades[0].price, dec!(100.5));
+        assert_eq!(trades[0].side, Side::Sell);
+        assert_eq!(trades[1].side, Side::Unknown);
+
+        let trades =
+            read_accounted_trades_jsonl(data.as_bytes(), &LoaderConfig::default()).unwrap();
+        assert_eq!(trades[0].buyer_id.as_deref(), Some("A1"));
+        assert_eq!(trades[0].seller_id.as_deref(), Some("7"));
+        assert_eq!(trades[1].buyer_id, None);
+    }
+
+    #[test]
+    fn test_timestamp_out_of_range() {
+        let config = LoaderConfig {
+            output_unit: Some(TimestampUnit::Nanos),
+            ..LoaderConfig::default()
+        };
+        for timestamp in ["1e25", "1e20"] {
+            let data = format!(r#"{{"price": 1, "quantity": 1, "timestamp": "{timestamp}"}}"#);
+            let err = read_trades_jsonl(data.as_bytes(), &config).unwrap_err();
+            assert!(matches!(err, LoadError::Parse { line: 1, .. }));
+        }
+
+        let data = "price,quantity,timestamp\n1,1,1e25\n";
+        let err = read_trades_csv(data.as_bytes(), &config).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 2, .. }));
+    }
+
+    #[test]
+    fn test_read_books_csv() {
+        let data = "timestamp,bid_price_1,bid_qty_1,bid_price_2,bid_qty_2,ask_price_1,ask_qty_1\n\
+                    1000,100,1.5,99.5,2,100.5,1\n\
+                    1001,100,1.0,,,100.5,3\n";
+
+        let books = read_books_csv(data.as_bytes(), &LoaderConfig::default()).unwrap();
+
+        assert_eq!(books.len(), 2);
+        assert_eq!(books[0].bids.len(), 2);
+        assert_eq!(books[0].bids[1].price, dec!(99.5));
+        assert_eq!(books[1].bids.len(), 1);
+        assert_eq!(books[1].asks[0].quantity, dec!(3));
+    }
+
+    #[test]
+    fn test_read_books_jsonl() {
+        let data = r#"{"timestamp": 1000, "bids": [["100", "1.5"], [99.5, 2]], "asks": [{"price": "100.5", "quantity": "1"}]}
+{"timestamp": 1001, "bids": [["100"]], "asks": []}
+"#;
+        let err = read_books_jsonl(data.as_bytes(), &LoaderConfig::default()).unwrap_err();
+        assert!(matches!(err, LoadError::Parse { line: 2, .. }));
+
+        let first = data.lines().next().unwrap();
+        let books = read_books_jsonl(first.as_bytes(), &LoaderConfig::default()).unwrap();
+        assert_eq!(books[0].bids[1].quantity, dec!(2));
+        assert_eq!(books[0].asks[0].price, dec!(100.5));
+    }
+
+    #[test]
+    fn test_format_from_path() {
+        assert_eq!(Format::from_path(Path::new("a.CSV")), Some(Format::Csv));
+        assert_eq!(
+            Format::from_path(Path::new("a.ndjson")),
+            Some(Format::JsonLines)
+        );
+        assert_eq!(Format::from_path(Path::new("a.bin")), None);
+    }
+}
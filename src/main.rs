@@ -1,9 +1,802 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use market_microstructure_analyzer::loader::{self, LoaderConfig, TimestampUnit, TradeColumns};
+use market_microstructure_analyzer::tape::{ClassificationMethod, ClassifierConfig};
 use market_microstructure_analyzer::*;
+use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use serde::Serialize;
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+/// Market microstructure analytics over captured trades and order books
+#[derive(Parser)]
+#[command(name = "market-analyzer", version, about)]
+struct Cli {
+    /// Output format
+    #[arg(long, short, value_enum, global = true, default_value_t = OutputFormat::Text)]
+    format: OutputFormat,
+
+    /// Runs the built-in demo when omitted
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OutputFormat {
+    Text,
+    Json,
+    Csv,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Spread, imbalance, VWAP and delta summary
+    Analyze {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Book levels used for imbalance (all when omitted)
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+    /// Volume profile with point of control and value area
+    Profile {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Price bucket size
+        #[arg(long, default_value = "1")]
+        tick_size: Decimal,
+        /// Fraction of volume in the value area
+        #[arg(long, default_value = "0.70")]
+        value_area: Decimal,
+    },
+    /// Run all pattern detectors
+    ///
+    /// Spoofing and icebergs come from the book history; the single-snapshot
+    /// and fill-count heuristics run only with --snapshot-spoof-threshold and
+    /// --min-fills.
+    Detect {
+        #[command(flatten)]
+        input: InputArgs,
+        #[command(flatten)]
+        thresholds: DetectArgs,
+    },
+    /// Stream a capture through the analytics, one row per trade
+    ///
+    /// Rows are written as they are produced; `--format json` writes one
+    /// JSON object per line instead of an array.
+    Replay {
+        #[command(flatten)]
+        input: InputArgs,
+        /// Book levels used for imbalance (all when omitted)
+        #[arg(long)]
+        depth: Option<usize>,
+    },
+}
+
+#[derive(Args)]
+struct InputArgs {
+    /// Trades file (.csv, .jsonl, .ndjson)
+    #[arg(long)]
+    trades: Option<PathBuf>,
+    /// Order book snapshots file (.csv, .jsonl, .ndjson)
+    #[arg(long)]
+    book: Option<PathBuf>,
+    /// Unit of input timestamps (s, ms, us, ns)
+    #[arg(long, default_value = "s")]
+    timestamp_unit: TimestampUnit,
+    /// Unit of timestamps in the output (s, ms, us, ns); defaults to
+    /// --timestamp-unit
+    #[arg(long)]
+    output_unit: Option<TimestampUnit>,
+    /// Trade price column
+    #[arg(long, default_value = "price")]
+    price_column: String,
+    /// Trade quantity column
+    #[arg(long, default_value = "quantity")]
+    quantity_column: String,
+    /// Trade aggressor side column
+    #[arg(long, default_value = "side")]
+    side_column: String,
+    /// Trade timestamp column
+    #[arg(long, default_value = "timestamp")]
+    timestamp_column: String,
+    /// Buyer account id column (optional in the data)
+    #[arg(long, default_value = "buyer_id")]
+    buyer_column: String,
+    /// Seller account id column (optional in the data)
+    #[arg(long, default_value = "seller_id")]
+    seller_column: String,
+    /// Infer missing trade sides (tick, reverse-tick, quote, lee-ready, emo, clnv)
+    #[arg(long)]
+    classify: Option<ClassificationMethod>,
+    /// Quote lag for --classify, in output timestamp units
+    #[arg(long, default_value_t = 0)]
+    quote_lag: i64,
+}
+
+impl InputArgs {
+    fn config(&self) -> LoaderConfig {
+        LoaderConfig {
+            trade_columns: TradeColumns {
+                price: self.price_column.clone(),
+                quantity: self.quantity_column.clone(),
+                side: Some(self.side_column.clone()),
+                timestamp: self.timestamp_column.clone(),
+                buyer_id: Some(self.buyer_column.clone()),
+                seller_id: Some(self.seller_column.clone()),
+            },
+            timestamp_unit: self.timestamp_unit,
+            output_unit: self.output_unit,
+            ..LoaderConfig::default()
+        }
+    }
+
+    fn load_trades(&self) -> Result<Vec<Trade>> {
+        let trades = self.load_accounted_trades()?;
+        Ok(trades.into_iter().map(|t| t.trade).collect())
+    }
+
+    fn load_accounted_trades(&self) -> Result<Vec<AccountedTrade>> {
+        let mut trades = match &self.trades {
+            Some(path) => loader::load_accounted_trades(path, &self.config())
+                .with_context(|| format!("loading trades from {}", path.display()))?,
+            None => return Ok(Vec::new()),
+        };
+        let Some(method) = self.classify else {
+            return Ok(trades);
+        };
+
+        let books = if method.uses_quotes() {
+            anyhow::ensure!(self.book.is_some(), "--classify {method} requires --book");
+            self.load_books()?
+        } else {
+            Vec::new()
+        };
+        let config = ClassifierConfig {
+            method,
+            quote_lag: self.quote_lag,
+        };
+        let tape: Vec<Trade> = trades.iter().map(|t| t.trade.clone()).collect();
+        for (accounted, trade) in trades
+            .iter_mut()
+            .zip(tape::infer_sides(&tape, &books, &config))
+        {
+            accounted.trade = trade;
+        }
+        Ok(trades)
+    }
+
+    fn load_books(&self) -> Result<Vec<OrderBook>> {
+        match &self.book {
+            Some(path) => loader::load_books(path, &self.config())
+                .with_context(|| format!("loading order books from {}", path.display())),
+            None => Ok(Vec::new()),
+        }
+    }
+
+    fn require_any(&self) -> Result<()> {
+        if self.trades.is_none() && self.book.is_none() {
+            anyhow::bail!("at least one of --trades or --book is required");
+        }
+        Ok(())
+    }
+}
+
+#[derive(Args)]
+struct DetectArgs {
+    /// Minimum refills of a traded-through level to flag an iceberg
+    #[arg(long, default_value_t = 2)]
+    min_refills: usize,
+    /// Minimum quantity added at a level to track it as a spoof
+    #[arg(long, default_value = "50")]
+    spoof_threshold: Decimal,
+    /// Tick size; spoofs are placed at least two ticks from the touch
+    #[arg(long, default_value = "1")]
+    tick_size: Decimal,
+    /// Longest spoof lifetime still flagged, in timestamp units
+    #[arg(long, default_value_t = 5)]
+    spoof_max_lifetime: i64,
+    /// Minimum levels placed and pulled together to flag layering
+    #[arg(long, default_value_t = 3)]
+    layering_levels: usize,
+    /// Also flag large non-touch levels of the last snapshot as spoofing
+    #[arg(long)]
+    snapshot_spoof_threshold: Option<Decimal>,
+    /// Also flag icebergs from repeated fills at one price, with this many fills
+    #[arg(long)]
+    min_fills: Option<usize>,
+    /// Price bucket for grouping fills with --min-fills
+    #[arg(long, default_value = "0.01")]
+    fill_tolerance: Decimal,
+    /// Minimum level size for support/resistance
+    #[arg(long, default_value = "10")]
+    level_threshold: Decimal,
+    /// Minimum traded volume for absorption
+    #[arg(long, default_value = "10")]
+    absorption_volume: Decimal,
+    /// Maximum price range for absorption
+    #[arg(long, default_value = "1")]
+    absorption_range: Decimal,
+    /// Maximum time between the legs of a wash pair, in timestamp units
+    #[arg(long, default_value_t = 1)]
+    wash_pair_window: i64,
+    /// Window for chaining wash pairs and for round trips, in timestamp units
+    #[arg(long, default_value_t = 60)]
+    wash_window: i64,
+    /// Quote stuffing window, in timestamp units
+    #[arg(long, default_value_t = 1)]
+    stuffing_window: i64,
+    /// Preceding windows averaged into the quote stuffing baseline
+    #[arg(long, default_value_t = 60)]
+    stuffing_baseline: usize,
+    /// Multiple of the baseline message rate that flags quote stuffing
+    #[arg(long, default_value = "10")]
+    stuffing_multiplier: Decimal,
+    /// Minimum book updates in a window to flag quote stuffing
+    #[arg(long, default_value_t = 50)]
+    stuffing_min_messages: usize,
+}
+
+#[derive(Serialize)]
+struct AnalyzeReport {
+    trades: usize,
+    buy_volume: Decimal,
+    sell_volume: Decimal,
+    delta: Decimal,
+    vwap: Option<Decimal>,
+    aggression_ratio: Decimal,
+    book_timestamp: Option<i64>,
+    spread: Option<Decimal>,
+    spread_pct: Option<Decimal>,
+    mid_price: Option<Decimal>,
+    weighted_mid_price: Option<Decimal>,
+    imbalance: Option<Decimal>,
+}
+
+#[derive(Serialize)]
+struct ProfileRow {
+    price: Decimal,
+    volume: Decimal,
+    poc: bool,
+    value_area: bool,
+}
+
+#[derive(Serialize, Default)]
+struct PatternRow {
+    pattern: &'static str,
+    price: Decimal,
+    /// Size, strength or volume, depending on the pattern
+    value: Option<Decimal>,
+    /// Aggressor side, or book side for snapshot-based detections
+    side: Option<String>,
+    /// Start and end of the pattern, when it spans time
+    start: Option<i64>,
+    end: Option<i64>,
+    lifetime: Option<i64>,
+    confidence: Option<Decimal>,
+    refills: Option<usize>,
+    peak_size: Option<Decimal>,
+    executed: Option<Decimal>,
+    hidden_executed: Option<Decimal>,
+    messages: Option<usize>,
+    cancels: Option<usize>,
+    account: Option<String>,
+    /// Indices of the involved trades in the input, space-separated
+    trades: Option<String>,
+}
+
+impl PatternRow {
+    /// Optional columns for text output
+    fn details(&self) -> String {
+        let mut details = String::new();
+        if let (Some(start), Some(end)) = (self.start, self.end) {
+            details.push_str(&format!("  {start}..{end}"));
+        }
+        if let Some(lifetime) = self.lifetime {
+            details.push_str(&format!("  lifetime {lifetime}"));
+        }
+        if let Some(confidence) = self.confidence {
+            details.push_str(&format!("  confidence {confidence}"));
+        }
+        if let Some(refills) = self.refills {
+            details.push_str(&format!("  {refills} refills"));
+        }
+        if let (Some(messages), Some(cancels)) = (self.messages, self.cancels) {
+            details.push_str(&format!("  {messages} messages  {cancels} cancels"));
+        }
+        for (label, value) in [
+            ("peak", self.peak_size),
+            ("executed", self.executed),
+            ("hidden", self.hidden_executed),
+        ] {
+            if let Some(value) = value {
+                details.push_str(&format!("  {label} {value}"));
+            }
+        }
+        if let Some(account) = &self.account {
+            details.push_str(&format!("  account {account}"));
+        }
+        if let Some(trades) = &self.trades {
+            details.push_str(&format!("  trades [{trades}]"));
+        }
+        details
+    }
+}
+
+/// Join trade indices into one CSV-friendly cell
+fn trade_list(indices: &[usize]) -> Option<String> {
+    let list: Vec<String> = indices.iter().map(usize::to_string).collect();
+    Some(list.join(" "))
+}
+
+impl From<&patterns::Pattern> for PatternRow {
+    fn from(pattern: &patterns::Pattern) -> Self {
+        use patterns::Pattern;
+        match *pattern {
+            Pattern::IcebergOrder {
+                price,
+                estimated_size,
+            } => PatternRow {
+                pattern: "iceberg",
+                price,
+                value: Some(estimated_size),
+                ..PatternRow::default()
+            },
+            Pattern::Spoofing { price, side } => PatternRow {
+                pattern: "spoofing",
+                price,
+                side: Some(side.to_string()),
+                ..PatternRow::default()
+            },
+            Pattern::Support { price, strength } => PatternRow {
+                pattern: "support",
+                price,
+                value: Some(strength),
+                ..PatternRow::default()
+            },
+            Pattern::Resistance { price, strength } => PatternRow {
+                pattern: "resistance",
+                price,
+                value: Some(strength),
+                ..PatternRow::default()
+            },
+            Pattern::Absorption { price, volume, .. } => PatternRow {
+                pattern: "absorption",
+                price,
+                value: Some(volume),
+                ..PatternRow::default()
+            },
+            Pattern::WashTrade {
+                price,
+                quantity,
+                ref trades,
+            } => PatternRow {
+                pattern: "wash",
+                price,
+                value: Some(quantity),
+                trades: trade_list(trades),
+                ..PatternRow::default()
+            },
+            Pattern::RoundTrip {
+                price,
+                volume,
+                ref account,
+                ref trades,
+            } => PatternRow {
+                pattern: "round-trip",
+                price,
+                value: Some(volume),
+                account: Some(account.clone()),
+                trades: trade_list(trades),
+                ..PatternRow::default()
+            },
+            Pattern::SelfTrade {
+                price,
+                quantity,
+                ref account,
+                ref trades,
+            } => PatternRow {
+                pattern: "self-trade",
+                price,
+                value: Some(quantity),
+                account: Some(account.clone()),
+                trades: trade_list(trades),
+                ..PatternRow::default()
+            },
+        }
+    }
+}
+
+impl From<&patterns::SpoofingDetection> for PatternRow {
+    fn from(d: &patterns::SpoofingDetection) -> Self {
+        PatternRow {
+            pattern: match d.kind {
+                patterns::SpoofingKind::Spoof => "spoof",
+                patterns::SpoofingKind::Layering => "layering",
+            },
+            price: d.prices.first().copied().unwrap_or_default(),
+            value: Some(d.size),
+            side: Some(d.side.to_string()),
+            start: Some(d.placed_at),
+            end: Some(d.cancelled_at),
+            lifetime: Some(d.lifetime),
+            confidence: Some(d.confidence),
+            ..PatternRow::default()
+        }
+    }
+}
+
+impl From<&patterns::IcebergDetection> for PatternRow {
+    fn from(d: &patterns::IcebergDetection) -> Self {
+        PatternRow {
+            pattern: "iceberg",
+            price: d.price,
+            side: Some(d.side.to_string()),
+            start: Some(d.first_seen),
+            end: Some(d.last_seen),
+            refills: Some(d.refills),
+            peak_size: Some(d.peak_size),
+            executed: Some(d.executed),
+            hidden_executed: Some(d.hidden_executed),
+            ..PatternRow::default()
+        }
+    }
+}
+
+impl From<&patterns::QuoteStuffingEvent> for PatternRow {
+    fn from(e: &patterns::QuoteStuffingEvent) -> Self {
+        PatternRow {
+            pattern: "stuffing",
+            price: e.price_range.0,
+            value: Some(e.trade_volume),
+            start: Some(e.start),
+            end: Some(e.end),
+            messages: Some(e.messages),
+            cancels: Some(e.cancels),
+            ..PatternRow::default()
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ReplayRow {
+    timestamp: i64,
+    price: Decimal,
+    quantity: Decimal,
+    side: Side,
+    vwap: Option<Decimal>,
+    cvd: Decimal,
+    spread: Option<Decimal>,
+    mid_price: Option<Decimal>,
+    imbalance: Option<Decimal>,
+}
+
+/// Write rows as CSV or a JSON array; text output is handled per command
+fn write_rows<T: Serialize>(rows: &[T], format: OutputFormat) -> Result<()> {
+    let stdout = io::stdout();
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(stdout.lock());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Json | OutputFormat::Text => {
+            let mut out = stdout.lock();
+            serde_json::to_writer_pretty(&mut out, rows)?;
+            writeln!(out)?;
+        }
+    }
+    Ok(())
+}
+
+fn fmt_opt(value: Option<Decimal>) -> String {
+    value.map_or_else(|| "-".to_string(), |v| v.round_dp(6).to_string())
+}
+
+fn run_analyze(input: &InputArgs, depth: Option<usize>, format: OutputFormat) -> Result<()> {
+    input.require_any()?;
+    let trades = input.load_trades()?;
+    let books = input.load_books()?;
+    let book = books.last();
+
+    let (buy_volume, sell_volume, _) = tape::calculate_trade_pressure(&trades);
+    let spread = book.and_then(orderbook::calculate_spread);
+    let report = AnalyzeReport {
+        trades: trades.len(),
+        buy_volume,
+        sell_volume,
+        delta: metrics::calculate_delta(&trades),
+        vwap: tape::calculate_vwap(&trades),
+        aggression_ratio: tape::calculate_aggression_ratio(&trades),
+        book_timestamp: book.map(|b| b.timestamp),
+        spread: spread.map(|(s, _)| s),
+        spread_pct: spread.map(|(_, p)| p),
+        mid_price: book.and_then(orderbook::mid_price),
+        weighted_mid_price: book.and_then(metrics::weighted_mid_price),
+        imbalance: book.map(|b| orderbook::calculate_imbalance(b, depth)),
+    };
+
+    if format == OutputFormat::Json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    if format == OutputFormat::Csv {
+        return write_rows(&[report], format);
+    }
+
+    println!("📈 Tape ({} trades)", report.trades);
+    println!("  🟢 Buy Volume: {}", report.buy_volume);
+    println!("  🔴 Sell Volume: {}", report.sell_volume);
+    println!("  📊 Delta: {}", report.delta);
+    println!("  📌 VWAP: {}", fmt_opt(report.vwap));
+    println!("  ⚡ Aggression: {:.4}", report.aggression_ratio);
+    if let Some(ts) = report.book_timestamp {
+        println!("\n📊 Order Book (timestamp {ts})");
+        println!(
+            "  💰 Spread: {} ({}%)",
+            fmt_opt(report.spread),
+            fmt_opt(report.spread_pct)
+        );
+        println!("  📍 Mid Price: {}", fmt_opt(report.mid_price));
+        println!("  ⚖️  Weighted Mid: {}", fmt_opt(report.weighted_mid_price));
+        println!("  ⚖️  Imbalance: {}", fmt_opt(report.imbalance));
+    }
+    Ok(())
+}
+
+fn run_profile(
+    input: &InputArgs,
+    tick_size: Decimal,
+    value_area: Decimal,
+    format: OutputFormat,
+) -> Result<()> {
+    anyhow::ensure!(tick_size > dec!(0), "--tick-size must be positive");
+    anyhow::ensure!(
+        value_area > dec!(0) && value_area <= dec!(1),
+        "--value-area must be in (0, 1]"
+    );
+    let trades = input.load_trades()?;
+    anyhow::ensure!(!trades.is_empty(), "profile requires --trades");
+
+    let profile = metrics::calculate_volume_profile_with_value_area(&trades, tick_size, value_area);
+    let levels: Vec<_> = profile.levels.iter().rev().map(|(&p, &v)| (p, v)).collect();
+
+    let in_value_area = |price: Decimal| match (profile.val, profile.vah) {
+        (Some(val), Some(vah)) => price >= val && price <= vah,
+        _ => false,
+    };
+    let rows: Vec<ProfileRow> = levels
+        .iter()
+        .map(|&(price, volume)| ProfileRow {
+            price,
+            volume,
+            poc: profile.poc == Some(price),
+            value_area: in_value_area(price),
+        })
+        .collect();
+
+    if format != OutputFormat::Text {
+        return write_rows(&rows, format);
+    }
+
+    println!(
+        "POC: {}  VAH: {}  VAL: {}",
+        fmt_opt(profile.poc),
+        fmt_opt(profile.vah),
+        fmt_opt(profile.val)
+    );
+    let max_volume = rows.iter().map(|r| r.volume).max().unwrap_or(dec!(1));
+    for row in &rows {
+        // All-zero volumes draw no bars
+        let width = row
+            .volume
+            .checked_div(max_volume)
+            .and_then(|share| (share * dec!(40)).round().try_into().ok())
+            .unwrap_or(0usize);
+        let marker = if row.poc {
+            "◀ POC"
+        } else if row.value_area {
+            "·"
+        } else {
+            ""
+        };
+        println!(
+            "  {:>12} │{:<40} {} {marker}",
+            row.price,
+            "█".repeat(width),
+            row.volume
+        );
+    }
+    Ok(())
+}
+
+fn run_detect(input: &InputArgs, t: &DetectArgs, format: OutputFormat) -> Result<()> {
+    input.require_any()?;
+    anyhow::ensure!(
+        t.fill_tolerance > dec!(0),
+        "--fill-tolerance must be positive"
+    );
+    let accounted = input.load_accounted_trades()?;
+    let trades: Vec<Trade> = accounted.iter().map(|t| t.trade.clone()).collect();
+    let books = input.load_books()?;
+
+    let mut rows: Vec<PatternRow> = Vec::new();
+    let mut found = Vec::new();
+    if !trades.is_empty() {
+        found.extend(patterns::detect_absorption(
+            &trades,
+            t.absorption_volume,
+            t.absorption_range,
+        ));
+        if let Some(min_fills) = t.min_fills {
+            found.extend(patterns::detect_iceberg_orders(
+                &trades,
+                min_fills,
+                t.fill_tolerance,
+            ));
+        }
+        found.extend(patterns::detect_wash_trading(
+            &accounted,
+            &patterns::WashTradingConfig {
+                pair_window: t.wash_pair_window,
+                window: t.wash_window,
+                ..Default::default()
+            },
+        ));
+    }
+    if let Some(book) = books.last() {
+        found.extend(patterns::detect_support_resistance(book, t.level_threshold));
+        if let Some(threshold) = t.snapshot_spoof_threshold {
+            found.extend(patterns::detect_spoofing(book, threshold));
+        }
+    }
+    rows.extend(found.iter().map(PatternRow::from));
+
+    let spoofing = patterns::SpoofingConfig {
+        max_lifetime: t.spoof_max_lifetime,
+        layering_min_levels: t.layering_levels,
+        ..patterns::SpoofingConfig::new(t.spoof_threshold, t.tick_size)
+    };
+    rows.extend(
+        patterns::detect_spoofing_history(&books, &trades, &spoofing)
+            .iter()
+            .map(PatternRow::from),
+    );
+    rows.extend(
+        patterns::detect_iceberg_replenishment(&books, &trades, t.min_refills)
+            .iter()
+            .map(PatternRow::from),
+    );
+    let stuffing = patterns::QuoteStuffingConfig {
+        window: t.stuffing_window,
+        baseline_windows: t.stuffing_baseline,
+        rate_multiplier: t.stuffing_multiplier,
+        min_messages: t.stuffing_min_messages,
+        ..Default::default()
+    };
+    rows.extend(
+        patterns::detect_quote_stuffing(&orderbook::level_updates(&books), &trades, &stuffing)
+            .iter()
+            .map(PatternRow::from),
+    );
+    if format != OutputFormat::Text {
+        return write_rows(&rows, format);
+    }
+
+    if rows.is_empty() {
+        println!("✓ No patterns detected");
+    }
+    for row in &rows {
+        let side = row
+            .side
+            .as_ref()
+            .map(|s| format!(" ({s})"))
+            .unwrap_or_default();
+        println!(
+            "🔍 {:<11} @ {}{side}  {}{}",
+            row.pattern,
+            row.price,
+            fmt_opt(row.value),
+            row.details()
+        );
+    }
+    Ok(())
+}
+
+fn run_replay(input: &InputArgs, depth: Option<usize>, format: OutputFormat) -> Result<()> {
+    let trades = input.load_trades()?;
+    anyhow::ensure!(!trades.is_empty(), "replay requires --trades");
+    let books = input.load_books()?;
+
+    let mut book_iter = books.iter().peekable();
+    let mut book: Option<&OrderBook> = None;
+    let (mut value, mut volume, mut cvd) = (dec!(0), dec!(0), dec!(0));
+
+    // Rows are produced lazily and written as they come
+    let rows = trades.iter().map(|trade| {
+        // Latest snapshot at or before the trade
+        while let Some(next) = book_iter.next_if(|b| b.timestamp <= trade.timestamp) {
+            book = Some(next);
+        }
+
+        value += trade.price * trade.quantity;
+        volume += trade.quantity;
+        cvd += trade.signed_quantity();
+
+        ReplayRow {
+            timestamp: trade.timestamp,
+            price: trade.price,
+            quantity: trade.quantity,
+            side: trade.side,
+            vwap: (volume > dec!(0)).then(|| value / volume),
+            cvd,
+            spread: book.and_then(orderbook::calculate_spread).map(|(s, _)| s),
+            mid_price: book.and_then(orderbook::mid_price),
+            imbalance: book.map(|b| orderbook::calculate_imbalance(b, depth)),
+        }
+    });
+
+    match format {
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(io::stdout().lock());
+            for row in rows {
+                writer.serialize(row)?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Json => {
+            // JSON lines rather than an array, so nothing is buffered
+            let mut out = io::stdout().lock();
+            for row in rows {
+                serde_json::to_writer(&mut out, &row)?;
+                writeln!(out)?;
+            }
+            out.flush()?;
+        }
+        OutputFormat::Text => {
+            println!(
+                "{:<14} {:>12} {:>10} {:<7} {:>12} {:>10} {:>8} {:>10}",
+                "Time", "Price", "Qty", "Side", "VWAP", "CVD", "Spread", "Imbalance"
+            );
+            for row in rows {
+                println!(
+                    "{:<14} {:>12} {:>10} {:<7} {:>12} {:>10} {:>8} {:>10}",
+                    row.timestamp,
+                    row.price,
+                    row.quantity,
+                    row.side,
+                    fmt_opt(row.vwap.map(|v| v.round_dp(2))),
+                    row.cvd,
+                    fmt_opt(row.spread),
+                    fmt_opt(row.imbalance.map(|i| i.round_dp(4)))
+                );
+            }
+        }
+    }
+    Ok(())
+}
 
 fn main() -> Result<()> {
     env_logger::init();
+    let cli = Cli::parse();
+
+    match &cli.command {
+        None => run_demo(),
+        Some(Command::Analyze { input, depth }) => run_analyze(input, *depth, cli.format),
+        Some(Command::Profile {
+            input,
+            tick_size,
+            value_area,
+        }) => run_profile(input, *tick_size, *value_area, cli.format),
+        Some(Command::Detect { input, thresholds }) => run_detect(input, thresholds, cli.format),
+        Some(Command::Replay { input, depth }) => run_replay(input, *depth, cli.format),
+    }
+}
+
+fn run_demo() -> Result<()> {
     println!("╔═══════════════════════════════════════════════════╗");
     println!("║   Market Microstructure Analyzer - Demo          ║");
     println!("╚═══════════════════════════════════════════════════╝\n");
@@ -46,19 +839,19 @@ fn main() -> Result<()> {
         Trade {
             price: dec!(50000.0),
             quantity: dec!(1.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435200,
         },
         Trade {
             price: dec!(50001.0),
             quantity: dec!(0.5),
-            side: "sell".to_string(),
+            side: Side::Sell,
             timestamp: 1696435201,
         },
         Trade {
             price: dec!(50002.0),
             quantity: dec!(2.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1696435202,
         },
     ];
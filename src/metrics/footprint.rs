@@ -0,0 +1,335 @@
+//! Footprint (Bid/Ask Volume-at-Price) Bars
+//!
+//! Splits each bar's volume by price bucket into buyer- and seller-initiated
+//! volume, and flags diagonal imbalances: ask volume at a price compared with
+//! bid volume one tick below (buying), and bid volume at a price compared
+//! with ask volume one tick above (selling). Both prices must have traded
+//! within the bar.
+
+use super::{point_of_control, price_bucket};
+use crate::bars::{Bar, BarBuilder, BarSpec};
+use crate::types::{Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Footprint settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FootprintConfig {
+    /// Price bucket size
+    pub tick_size: Decimal,
+    /// Minimum ratio between diagonal volumes to flag an imbalance
+    pub imbalance_ratio: Decimal,
+    /// Minimum consecutive imbalanced prices to report a stack
+    pub stacked_levels: usize,
+}
+
+impl Default for FootprintConfig {
+    fn default() -> Self {
+        Self {
+            tick_size: dec!(1),
+            imbalance_ratio: dec!(3),
+            stacked_levels: 3,
+        }
+    }
+}
+
+/// Volume traded at one price within a bar
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FootprintLevel {
+    pub price: Decimal,
+    /// Seller-initiated volume (traded on the bid)
+    pub sell_volume: Decimal,
+    /// Buyer-initiated volume (traded on the ask)
+    pub buy_volume: Decimal,
+    /// Total volume, including trades with an unknown side
+    pub volume: Decimal,
+    pub delta: Decimal,
+    /// Buy volume dominates the bid volume one tick below
+    pub buy_imbalance: bool,
+    /// Sell volume dominates the ask volume one tick above
+    pub sell_imbalance: bool,
+}
+
+/// Consecutive prices imbalanced in the same direction
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StackedImbalance {
+    pub side: Side,
+    pub low: Decimal,
+    pub high: Decimal,
+    pub levels: usize,
+}
+
+/// A bar with its volume-at-price breakdown
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FootprintBar {
+    pub bar: Bar,
+    /// Levels sorted by price, highest first
+    pub levels: Vec<FootprintLevel>,
+    /// Price with the most volume, ties broken as in
+    /// [`super::calculate_volume_profile`]
+    pub poc: Option<Decimal>,
+    pub stacked_imbalances: Vec<StackedImbalance>,
+}
+
+/// Whether `a` dominates `b` by at least `ratio` (any volume beats none)
+fn dominates(a: Decimal, b: Decimal, ratio: Decimal) -> bool {
+    a > dec!(0) && a >= b * ratio
+}
+
+impl FootprintBar {
+    fn build(
+        bar: Bar,
+        levels: BTreeMap<Decimal, FootprintLevel>,
+        config: &FootprintConfig,
+    ) -> Self {
+        let tick = config.tick_size;
+        let mut levels: Vec<FootprintLevel> = levels.into_values().collect();
+
+        // Levels are ascending here. The diagonal price must have traded in
+        // the bar, so the extremes are never compared against nothing.
+        let at = |levels: &[FootprintLevel], price: Decimal| {
+            levels
+                .binary_search_by(|l| l.price.cmp(&price))
+                .ok()
+                .map(|i| (levels[i].buy_volume, levels[i].sell_volume))
+        };
+        for i in 0..levels.len() {
+            let price = levels[i].price;
+            if let Some((_, below_sell)) = at(&levels, price - tick) {
+                levels[i].buy_imbalance =
+                    dominates(levels[i].buy_volume, below_sell, config.imbalance_ratio);
+            }
+            if let Some((above_buy, _)) = at(&levels, price + tick) {
+                levels[i].sell_imbalance =
+                    dominates(levels[i].sell_volume, above_buy, config.imbalance_ratio);
+            }
+        }
+
+        let mut stacked_imbalances = stacks(&levels, tick, config.stacked_levels, Side::Buy);
+        stacked_imbalances.extend(stacks(&levels, tick, config.stacked_levels, Side::Sell));
+
+        let poc = point_of_control(levels.iter().map(|l| (l.price, l.volume)));
+        levels.reverse();
+
+        FootprintBar {
+            bar,
+            levels,
+            poc,
+            stacked_imbalances,
+        }
+    }
+}
+
+/// Runs of at least `min_levels` tick-adjacent imbalances on one side
+fn stacks(
+    ascending: &[FootprintLevel],
+    tick: Decimal,
+    min_levels: usize,
+    side: Side,
+) -> Vec<StackedImbalance> {
+    let mut result = Vec::new();
+    let mut run: Option<StackedImbalance> = None;
+
+    for level in ascending {
+        let flagged = match side {
+            Side::Buy => level.buy_imbalance,
+            _ => level.sell_imbalance,
+        };
+        run = match run.take() {
+            Some(mut r) if flagged && level.price == r.high + tick => {
+                r.high = level.price;
+                r.levels += 1;
+                Some(r)
+            }
+            previous => {
+                result.extend(previous.filter(|r| r.levels >= min_levels.max(1)));
+                flagged.then_some(StackedImbalance {
+                    side,
+                    low: level.price,
+                    high: level.price,
+                    levels: 1,
+                })
+            }
+        };
+    }
+    result.extend(run.filter(|r| r.levels >= min_levels.max(1)));
+    result
+}
+
+/// Incremental footprint builder on top of [`BarBuilder`]
+#[derive(Debug, Clone)]
+pub struct FootprintBuilder {
+    bars: BarBuilder,
+    time_based: bool,
+    config: FootprintConfig,
+    levels: BTreeMap<Decimal, FootprintLevel>,
+}
+
+impl FootprintBuilder {
+    /// Create a builder sampling bars by `spec`
+    ///
+    /// # Panics
+    /// If `config.tick_size` is not positive
+    #[must_use]
+    pub fn new(spec: BarSpec, config: FootprintConfig) -> Self {
+        assert!(
+            config.tick_size > dec!(0),
+            "footprint tick size must be positive"
+        );
+        Self {
+            bars: BarBuilder::new(spec),
+            time_based: matches!(spec, BarSpec::Time(_)),
+            config,
+            levels: BTreeMap::new(),
+        }
+    }
+
+    fn add(&mut self, trade: &Trade) {
+        let price = price_bucket(trade.price, self.config.tick_size);
+        let level = self.levels.entry(price).or_insert_with(|| FootprintLevel {
+            price,
+            ..FootprintLevel::default()
+        });
+        match trade.side {
+            Side::Buy => level.buy_volume += trade.quantity,
+            Side::Sell => level.sell_volume += trade.quantity,
+            Side::Unknown => {}
+        }
+        level.volume += trade.quantity;
+        level.delta += trade.signed_quantity();
+    }
+
+    fn finish(&mut self, bar: Bar) -> FootprintBar {
+        FootprintBar::build(bar, std::mem::take(&mut self.levels), &self.config)
+    }
+
+    /// Feed one trade, returning the footprint of the bar it completes
+    pub fn update(&mut self, trade: &Trade) -> Option<FootprintBar> {
+        if self.time_based {
+            // A time bar closes when a trade opens the next interval
+            let completed = self.bars.update(trade).map(|bar| self.finish(bar));
+            self.add(trade);
+            completed
+        } else {
+            self.add(trade);
+            let bar = self.bars.update(trade)?;
+            Some(self.finish(bar))
+        }
+    }
+
+    /// Close and return the partial footprint
+    pub fn flush(&mut self) -> Option<FootprintBar> {
+        let bar = self.bars.flush()?;
+        Some(self.finish(bar))
+    }
+}
+
+/// Build footprint bars from a trade slice (should be sorted by timestamp)
+///
+/// # Panics
+/// If `config.tick_size` is not positive
+#[must_use]
+pub fn build_footprints(
+    trades: &[Trade],
+    spec: BarSpec,
+    config: FootprintConfig,
+) -> Vec<FootprintBar> {
+    let mut builder = FootprintBuilder::new(spec, config);
+    let mut bars: Vec<FootprintBar> = trades.iter().filter_map(|t| builder.update(t)).collect();
+    bars.extend(builder.flush());
+    bars
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::calculate_delta;
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    fn rally() -> Vec<Trade> {
+        vec![
+            trade(dec!(100), dec!(1), Side::Sell, 0),
+            trade(dec!(100), dec!(2), Side::Buy, 1),
+            trade(dec!(101), dec!(6), Side::Buy, 2),
+            trade(dec!(101), dec!(1), Side::Sell, 3),
+            trade(dec!(102), dec!(9), Side::Buy, 4),
+            trade(dec!(103), dec!(4), Side::Buy, 5),
+            trade(dec!(103), dec!(5), Side::Unknown, 6),
+            trade(dec!(104), dec!(2), Side::Sell, 70),
+        ]
+    }
+
+    #[test]
+    fn test_footprint_levels() {
+        let trades = rally();
+        let bars = build_footprints(&trades, BarSpec::Time(60), FootprintConfig::default());
+
+        assert_eq!(bars.len(), 2);
+        let fp = &bars[0];
+        let prices: Vec<_> = fp.levels.iter().map(|l| l.price).collect();
+        assert_eq!(prices, vec![dec!(103), dec!(102), dec!(101), dec!(100)]);
+
+        let top = &fp.levels[0];
+        assert_eq!(top.buy_volume, dec!(4));
+        assert_eq!(top.volume, dec!(9));
+        // 102 and 103 tie at 9; 102 is closer to the middle of the range
+        assert_eq!(fp.poc, Some(dec!(102)));
+
+        let delta: Decimal = fp.levels.iter().map(|l| l.delta).sum();
+        assert_eq!(delta, calculate_delta(&trades[..7]));
+        assert_eq!(delta, fp.bar.delta);
+    }
+
+    #[test]
+    fn test_stacked_buy_imbalances() {
+        let bars = build_footprints(&rally(), BarSpec::Time(60), FootprintConfig::default());
+        let fp = &bars[0];
+
+        // 101: 6 vs 1 below, 102: 9 vs 1 below, 103: 4 vs 0 below
+        assert!(fp
+            .levels
+            .iter()
+            .filter(|l| l.price > dec!(100))
+            .all(|l| l.buy_imbalance));
+        assert!(!fp.levels[3].buy_imbalance);
+        assert_eq!(
+            fp.stacked_imbalances,
+            vec![StackedImbalance {
+                side: Side::Buy,
+                low: dec!(101),
+                high: dec!(103),
+                levels: 3,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_tick_footprints() {
+        let bars = build_footprints(&rally(), BarSpec::Tick(4), FootprintConfig::default());
+
+        assert_eq!(bars.len(), 2);
+        assert_eq!(bars[0].bar.trade_count, 4);
+        let volume: Decimal = bars[0].levels.iter().map(|l| l.volume).sum();
+        assert_eq!(volume, bars[0].bar.volume);
+    }
+
+    #[test]
+    #[should_panic(expected = "tick size must be positive")]
+    fn test_zero_tick_size_is_rejected() {
+        let config = FootprintConfig {
+            tick_size: dec!(0),
+            ..FootprintConfig::default()
+        };
+        let _ = FootprintBuilder::new(BarSpec::Tick(1), config);
+    }
+}
@@ -0,0 +1,313 @@
+//! Liquidity Estimators from Trades Only
+//!
+//! Spread and illiquidity measures that need no quotes: Roll's implied
+//! spread, the Corwin-Schultz high-low spread, Amihud's illiquidity ratio and
+//! Kyle's lambda. Each returns a [`LiquidityEstimate`] carrying the sample
+//! size and sign diagnostics alongside the value.
+
+use crate::bars::Bar;
+use crate::stats::ols;
+use crate::types::Trade;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// An estimate with its diagnostics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LiquidityEstimate {
+    /// The estimate, None when it is undefined for the sample
+    pub value: Option<Decimal>,
+    /// Observations used
+    pub samples: usize,
+    /// Goodness of fit, for regression-based estimators
+    pub r_squared: Option<Decimal>,
+    /// The raw statistic has the wrong sign for the model
+    pub invalid_sign: bool,
+    /// Observations with the wrong sign (floored at zero where applicable)
+    pub invalid_samples: usize,
+}
+
+/// Price change of each bar: close minus the previous close (its open for
+/// the first bar)
+fn bar_changes(bars: &[Bar]) -> impl Iterator<Item = (Decimal, &Bar)> {
+    bars.iter().enumerate().map(move |(i, bar)| {
+        let previous = i.checked_sub(1).map_or(bar.open, |j| bars[j].close);
+        (bar.close - previous, bar)
+    })
+}
+
+/// Estimate Roll's implied spread
+///
+/// Bid-ask bounce makes consecutive trade price changes negatively
+/// correlated; the spread is `2 sqrt(-cov(dP_t, dP_t-1))`.
+///
+/// # Arguments
+/// * `trades` - Executed trades, sorted by timestamp
+///
+/// # Returns
+/// LiquidityEstimate in price units. The value is None and `invalid_sign`
+/// set when the serial covariance is not negative.
+#[must_use]
+pub fn roll_spread(trades: &[Trade]) -> LiquidityEstimate {
+    let changes: Vec<f64> = trades
+        .windows(2)
+        .map(|w| (w[1].price - w[0].price).to_f64().unwrap_or(0.0))
+        .collect();
+    let pairs: Vec<(f64, f64)> = changes.windows(2).map(|w| (w[0], w[1])).collect();
+    if pairs.len() < 2 {
+        return LiquidityEstimate {
+            samples: pairs.len(),
+            ..LiquidityEstimate::default()
+        };
+    }
+
+    let n = pairs.len() as f64;
+    let mean_lag = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let covariance = pairs
+        .iter()
+        .map(|(lag, change)| (lag - mean_lag) * (change - mean))
+        .sum::<f64>()
+        / (n - 1.0);
+
+    let invalid_sign = covariance >= 0.0;
+    LiquidityEstimate {
+        value: if invalid_sign {
+            None
+        } else {
+            Decimal::from_f64(2.0 * (-covariance).sqrt())
+        },
+        samples: pairs.len(),
+        r_squared: None,
+        invalid_sign,
+        invalid_samples: 0,
+    }
+}
+
+/// Estimate the Corwin-Schultz high-low spread
+///
+/// Uses each pair of consecutive bars; negative pair estimates are floored
+/// at zero before averaging, as in the original paper.
+///
+/// # Arguments
+/// * `bars` - Consecutive bars (typically daily)
+///
+/// # Returns
+/// LiquidityEstimate as a fraction of price. `invalid_sign` is set when the
+/// average before flooring is negative.
+#[must_use]
+pub fn corwin_schultz_spread(bars: &[Bar]) -> LiquidityEstimate {
+    let k = 3.0 - 2.0 * std::f64::consts::SQRT_2;
+    let log_range = |high: Decimal, low: Decimal| -> Option<f64> {
+        let ratio = (high / low).to_f64()?;
+        Some(ratio.ln().powi(2))
+    };
+
+    let estimates: Vec<f64> = bars
+        .windows(2)
+        .filter(|w| w[0].low > dec!(0) && w[1].low > dec!(0))
+        .filter_map(|w| {
+            let beta = log_range(w[0].high, w[0].low)? + log_range(w[1].high, w[1].low)?;
+            let gamma = log_range(w[0].high.max(w[1].high), w[0].low.min(w[1].low))?;
+            let alpha = ((2.0 * beta).sqrt() - beta.sqrt()) / k - (gamma / k).sqrt();
+            Some(2.0 * (alpha.exp() - 1.0) / (1.0 + alpha.exp()))
+        })
+        .collect();
+    if estimates.is_empty() {
+        return LiquidityEstimate::default();
+    }
+
+    let n = estimates.len() as f64;
+    let raw = estimates.iter().sum::<f64>() / n;
+    let floored = estimates.iter().map(|s| s.max(0.0)).sum::<f64>() / n;
+    LiquidityEstimate {
+        value: Decimal::from_f64(floored),
+        samples: estimates.len(),
+        r_squared: None,
+        invalid_sign: raw < 0.0,
+        invalid_samples: estimates.iter().filter(|s| **s < 0.0).count(),
+    }
+}
+
+/// Calculate Amihud's illiquidity ratio
+///
+/// Average of `|return| / dollar volume` over bars with traded notional.
+///
+/// # Arguments
+/// * `bars` - Consecutive bars
+///
+/// # Returns
+/// LiquidityEstimate (return per unit of notional; often scaled by 1e6)
+#[must_use]
+pub fn amihud_illiquidity(bars: &[Bar]) -> LiquidityEstimate {
+    let ratios: Vec<Decimal> = bar_changes(bars)
+        .filter(|(_, bar)| bar.notional > dec!(0) && bar.close > dec!(0))
+        .map(|(change, bar)| {
+            let previous = bar.close - change;
+            if previous.is_zero() {
+                dec!(0)
+            } else {
+                (change / previous).abs() / bar.notional
+            }
+        })
+        .collect();
+    if ratios.is_empty() {
+        return LiquidityEstimate::default();
+    }
+
+    LiquidityEstimate {
+        value: Some(ratios.iter().sum::<Decimal>() / Decimal::from(ratios.len())),
+        samples: ratios.len(),
+        ..LiquidityEstimate::default()
+    }
+}
+
+/// Estimate Kyle's lambda
+///
+/// Regresses each bar's price change on its signed volume (the bar delta),
+/// so trades need known sides.
+///
+/// # Arguments
+/// * `bars` - Consecutive bars
+///
+/// # Returns
+/// LiquidityEstimate with the slope (price change per unit of net volume)
+/// and R². `invalid_sign` is set when the slope is negative.
+#[must_use]
+pub fn kyle_lambda(bars: &[Bar]) -> LiquidityEstimate {
+    let (y, x): (Vec<f64>, Vec<Vec<f64>>) = bar_changes(bars)
+        .map(|(change, bar)| {
+            (
+                change.to_f64().unwrap_or(0.0),
+                vec![bar.delta.to_f64().unwrap_or(0.0)],
+            )
+        })
+        .unzip();
+    let Some(fit) = ols(&y, &x) else {
+        return LiquidityEstimate {
+            samples: y.len(),
+            ..LiquidityEstimate::default()
+        };
+    };
+
+    let slope = fit.coefficients[1];
+    LiquidityEstimate {
+        value: Decimal::from_f64(slope),
+        samples: fit.samples,
+        r_squared: Decimal::from_f64(fit.r_squared),
+        invalid_sign: slope < 0.0,
+        invalid_samples: 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bars::{build_bars, BarSpec};
+    use crate::types::Side;
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_roll_spread() {
+        // Pure bounce between 100 and 101
+        let bounce: Vec<Trade> = (0..20)
+            .map(|i| trade(dec!(100) + Decimal::from(i % 2), dec!(1), Side::Unknown, i))
+            .collect();
+        let estimate = roll_spread(&bounce);
+        assert!(!estimate.invalid_sign);
+        assert_eq!(estimate.samples, 18);
+        let spread = estimate.value.unwrap();
+        assert!((spread - dec!(2)).abs() < dec!(0.2));
+
+        // A steady trend has positive serial covariance
+        let trend: Vec<Trade> = (0..10)
+            .map(|i| trade(Decimal::from(100 + i * i), dec!(1), Side::Unknown, i))
+            .collect();
+        let estimate = roll_spread(&trend);
+        assert!(estimate.invalid_sign);
+        assert_eq!(estimate.value, None);
+    }
+
+    fn bar(open: Decimal, high: Decimal, low: Decimal, close: Decimal, delta: Decimal) -> Bar {
+        let mut bar =
+            build_bars(&[trade(open, dec!(10), Side::Unknown, 0)], BarSpec::Tick(1)).remove(0);
+        bar.high = high;
+        bar.low = low;
+        bar.close = close;
+        bar.delta = delta;
+        bar.notional = close * dec!(10);
+        bar
+    }
+
+    #[test]
+    fn test_corwin_schultz() {
+        // Overlapping ranges of the same width: the range is mostly spread
+        let bars = vec![
+            bar(dec!(100), dec!(101), dec!(99), dec!(100), dec!(0)),
+            bar(dec!(100), dec!(101), dec!(99), dec!(100), dec!(0)),
+            bar(dec!(100), dec!(101), dec!(99), dec!(100), dec!(0)),
+        ];
+        let estimate = corwin_schultz_spread(&bars);
+        assert_eq!(estimate.samples, 2);
+        assert!(estimate.value.unwrap() > dec!(0));
+        assert!(!estimate.invalid_sign);
+
+        // Non-overlapping ranges read as volatility, not spread
+        let trending = vec![
+            bar(dec!(100), dec!(101), dec!(100), dec!(101), dec!(0)),
+            bar(dec!(105), dec!(106), dec!(105), dec!(106), dec!(0)),
+        ];
+        let estimate = corwin_schultz_spread(&trending);
+        assert_eq!(estimate.value, Some(dec!(0)));
+        assert!(estimate.invalid_sign);
+        assert_eq!(estimate.invalid_samples, 1);
+    }
+
+    #[test]
+    fn test_amihud() {
+        let bars = vec![
+            bar(dec!(100), dec!(100), dec!(100), dec!(100), dec!(0)),
+            bar(dec!(100), dec!(102), dec!(100), dec!(102), dec!(0)),
+        ];
+        let estimate = amihud_illiquidity(&bars);
+        assert_eq!(estimate.samples, 2);
+        // (0 + 0.02 / 1020) / 2
+        assert_eq!(estimate.value, Some(dec!(0.02) / dec!(1020) / dec!(2)));
+    }
+
+    #[test]
+    fn test_kyle_lambda() {
+        let bars: Vec<Bar> = [(10, 1.0), (-20, -2.1), (5, 0.4), (0, 0.1), (15, 1.5)]
+            .into_iter()
+            .scan(dec!(100), |close, (delta, change)| {
+                let open = *close;
+                *close += Decimal::from_f64(change).unwrap();
+                Some(bar(
+                    open,
+                    open.max(*close),
+                    open.min(*close),
+                    *close,
+                    Decimal::from(delta),
+                ))
+            })
+            .collect();
+
+        let estimate = kyle_lambda(&bars);
+        assert_eq!(estimate.samples, 5);
+        let lambda = estimate.value.unwrap();
+        assert!((lambda - dec!(0.1)).abs() < dec!(0.01));
+        assert!(estimate.r_squared.unwrap() > dec!(0.95));
+        assert!(!estimate.invalid_sign);
+
+        assert_eq!(kyle_lambda(&bars[..2]).value, None);
+    }
+}
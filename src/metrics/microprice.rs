@@ -0,0 +1,345 @@
+//! Microprice and Multi-Level Fair Value
+//!
+//! Stoikov's microprice is the mid plus an adjustment that depends on the
+//! book state, here the top-of-book imbalance bucket and the spread in
+//! ticks. The adjustment is the expected sum of future mid changes, fitted
+//! from the state transitions of consecutive snapshots: with `Q` the
+//! transitions that keep the mid, `T` those that move it and `R` the
+//! expected move,
+//!
+//! - `G1 = (I - Q)^-1 R` and `B = (I - Q)^-1 T`
+//! - adjustment = `G1 + B G1 + B² G1 + ...`
+//!
+//! The data is symmetrized (every transition is also counted mirrored, with
+//! `1 - imbalance` and the opposite move) so the table is unbiased.
+//! [`depth_weighted_mid`] extends [`super::weighted_mid_price`] to N levels.
+
+use crate::orderbook::mid_price;
+use crate::stats::solve;
+use crate::types::{Level, OrderBook};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Discretization for [`calibrate_microprice`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicropriceConfig {
+    pub tick_size: Decimal,
+    /// Buckets of `bid_qty / (bid_qty + ask_qty)` over [0, 1]
+    pub imbalance_buckets: usize,
+    /// Widest spread, in ticks, kept as a state; wider books are skipped
+    pub max_spread_ticks: usize,
+    /// Terms of the series `G1 + B G1 + B² G1 + ...`
+    pub iterations: usize,
+}
+
+impl MicropriceConfig {
+    /// Ten imbalance buckets, spreads of one and two ticks, six terms
+    #[must_use]
+    pub fn new(tick_size: Decimal) -> Self {
+        Self {
+            tick_size,
+            imbalance_buckets: 10,
+            max_spread_ticks: 2,
+            iterations: 6,
+        }
+    }
+}
+
+/// Fitted microprice adjustments, serializable for reuse
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MicropriceTable {
+    pub tick_size: Decimal,
+    pub imbalance_buckets: usize,
+    pub max_spread_ticks: usize,
+    /// Adjustment to the mid, indexed `[spread_ticks - 1][imbalance_bucket]`
+    pub adjustments: Vec<Vec<Decimal>>,
+    /// Snapshot transitions used (before symmetrization)
+    pub samples: usize,
+}
+
+/// Top-of-book state: spread in ticks and imbalance
+fn book_state(orderbook: &OrderBook, tick_size: Decimal) -> Option<(usize, f64)> {
+    let (bid, ask) = (orderbook.bids.first()?, orderbook.asks.first()?);
+    if tick_size <= dec!(0) {
+        return None;
+    }
+    let depth = bid.quantity + ask.quantity;
+    if depth <= dec!(0) {
+        return None;
+    }
+    let ticks = ((ask.price - bid.price) / tick_size).round().to_usize()?;
+    Some((ticks, (bid.quantity / depth).to_f64()?))
+}
+
+fn bucket(imbalance: f64, buckets: usize) -> usize {
+    ((imbalance * buckets as f64) as usize).min(buckets - 1)
+}
+
+impl MicropriceTable {
+    /// State index of a book, None when its spread is outside the table
+    fn state(&self, orderbook: &OrderBook, imbalance_flip: bool) -> Option<usize> {
+        let (ticks, imbalance) = book_state(orderbook, self.tick_size)?;
+        if ticks == 0 || ticks > self.max_spread_ticks || self.imbalance_buckets == 0 {
+            return None;
+        }
+        let imbalance = if imbalance_flip {
+            1.0 - imbalance
+        } else {
+            imbalance
+        };
+        Some((ticks - 1) * self.imbalance_buckets + bucket(imbalance, self.imbalance_buckets))
+    }
+
+    /// Adjustment to the mid for a book
+    ///
+    /// # Returns
+    /// The fitted adjustment, or None when the book is one-sided or its
+    /// spread is outside the table
+    #[must_use]
+    pub fn adjustment(&self, orderbook: &OrderBook) -> Option<Decimal> {
+        let state = self.state(orderbook, false)?;
+        self.adjustments
+            .get(state / self.imbalance_buckets)?
+            .get(state % self.imbalance_buckets)
+            .copied()
+    }
+
+    /// Microprice of a book: mid plus the fitted adjustment
+    #[must_use]
+    pub fn microprice(&self, orderbook: &OrderBook) -> Option<Decimal> {
+        Some(mid_price(orderbook)? + self.adjustment(orderbook)?)
+    }
+}
+
+/// Fit a microprice adjustment table from snapshots
+///
+/// # Arguments
+/// * `books` - Consecutive snapshots sorted by timestamp
+/// * `config` - State discretization
+///
+/// # Returns
+/// MicropriceTable, or None without usable transitions. States never
+/// observed, or from which the mid was never seen to change, get a zero
+/// adjustment.
+#[must_use]
+pub fn calibrate_microprice(
+    books: &[OrderBook],
+    config: &MicropriceConfig,
+) -> Option<MicropriceTable> {
+    let mut table = MicropriceTable {
+        tick_size: config.tick_size,
+        imbalance_buckets: config.imbalance_buckets,
+        max_spread_ticks: config.max_spread_ticks,
+        adjustments: Vec::new(),
+        samples: 0,
+    };
+    let n = config.imbalance_buckets * config.max_spread_ticks;
+    if n == 0 {
+        return None;
+    }
+
+    let mut counts = vec![0.0; n];
+    let mut stay = vec![vec![0.0; n]; n];
+    let mut moves = vec![vec![0.0; n]; n];
+    let mut expected_move = vec![0.0; n];
+
+    for pair in books.windows(2) {
+        let (Some(mid0), Some(mid1)) = (mid_price(&pair[0]), mid_price(&pair[1])) else {
+            continue;
+        };
+        let Some(change) = (mid1 - mid0).to_f64() else {
+            continue;
+        };
+        for flip in [false, true] {
+            let (Some(x), Some(y)) = (table.state(&pair[0], flip), table.state(&pair[1], flip))
+            else {
+                break;
+            };
+            let change = if flip { -change } else { change };
+            counts[x] += 1.0;
+            if change == 0.0 {
+                stay[x][y] += 1.0;
+            } else {
+                moves[x][y] += 1.0;
+                expected_move[x] += change;
+            }
+            if !flip {
+                table.samples += 1;
+            }
+        }
+    }
+    if table.samples == 0 {
+        return None;
+    }
+
+    // States from which no mid change is ever reached get no adjustment;
+    // dropping their transitions keeps I - Q invertible
+    let mut reaches_move: Vec<bool> = moves
+        .iter()
+        .map(|row| row.iter().any(|&m| m > 0.0))
+        .collect();
+    loop {
+        let before = reaches_move.clone();
+        for x in 0..n {
+            if !reaches_move[x] {
+                reaches_move[x] = (0..n).any(|y| stay[x][y] > 0.0 && before[y]);
+            }
+        }
+        if reaches_move == before {
+            break;
+        }
+    }
+
+    // Row-normalized I - Q, and [R | T] as right-hand sides
+    let mut system = vec![vec![0.0; n]; n];
+    let mut rhs = vec![vec![0.0; n + 1]; n];
+    for x in 0..n {
+        system[x][x] = 1.0;
+        if !reaches_move[x] {
+            continue;
+        }
+        for y in 0..n {
+            system[x][y] -= stay[x][y] / counts[x];
+            rhs[x][y + 1] = moves[x][y] / counts[x];
+        }
+        rhs[x][0] = expected_move[x] / counts[x];
+    }
+
+    // Column 0 is G1, the rest is B
+    let solution = solve(&system, &rhs)?;
+    let g1: Vec<f64> = solution.iter().map(|row| row[0]).collect();
+    let mut term = g1.clone();
+    let mut total = g1;
+    for _ in 1..config.iterations.max(1) {
+        term = solution
+            .iter()
+            .map(|row| row[1..].iter().zip(&term).map(|(b, g)| b * g).sum())
+            .collect();
+        for (t, g) in total.iter_mut().zip(&term) {
+            *t += g;
+        }
+    }
+
+    table.adjustments = total
+        .chunks(config.imbalance_buckets)
+        .map(|row| {
+            row.iter()
+                .map(|&g| Decimal::from_f64(g).unwrap_or_default())
+                .collect()
+        })
+        .collect();
+    Some(table)
+}
+
+/// Volume-weighted average price and total quantity of the first levels
+fn side_average(levels: &[Level], depth: usize) -> Option<(Decimal, Decimal)> {
+    let quantity: Decimal = levels.iter().take(depth).map(|l| l.quantity).sum();
+    if quantity <= dec!(0) {
+        return None;
+    }
+    let notional: Decimal = levels
+        .iter()
+        .take(depth)
+        .map(|l| l.price * l.quantity)
+        .sum();
+    Some((notional / quantity, quantity))
+}
+
+/// Calculate the depth-weighted mid over N levels
+///
+/// Like [`super::weighted_mid_price`], but each side is summarized by the
+/// volume-weighted price and total quantity of its first `levels` levels.
+/// With one level it equals the weighted mid.
+///
+/// # Arguments
+/// * `orderbook` - The order book to analyze
+/// * `levels` - Levels per side
+///
+/// # Returns
+/// The depth-weighted mid, or None when either side has no quantity
+#[must_use]
+pub fn depth_weighted_mid(orderbook: &OrderBook, levels: usize) -> Option<Decimal> {
+    let (bid, bid_quantity) = side_average(&orderbook.bids, levels)?;
+    let (ask, ask_quantity) = side_average(&orderbook.asks, levels)?;
+    Some((bid * ask_quantity + ask * bid_quantity) / (bid_quantity + ask_quantity))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::weighted_mid_price;
+
+    fn book(mid: Decimal, bid_qty: Decimal, ask_qty: Decimal) -> OrderBook {
+        OrderBook {
+            bids: vec![Level {
+                price: mid - dec!(0.5),
+                quantity: bid_qty,
+            }],
+            asks: vec![Level {
+                price: mid + dec!(0.5),
+                quantity: ask_qty,
+            }],
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_calibrate_microprice() {
+        // Bid-heavy books are always followed by an uptick, ask-heavy books
+        // by a downtick; balanced books keep the mid
+        let cycle = [
+            (dec!(100.5), dec!(17), dec!(3)),
+            (dec!(101.5), dec!(5), dec!(5)),
+            (dec!(101.5), dec!(3), dec!(17)),
+            (dec!(100.5), dec!(5), dec!(5)),
+        ];
+        let books: Vec<OrderBook> = (0..10)
+            .flat_map(|_| cycle.iter().map(|&(m, b, a)| book(m, b, a)))
+            .collect();
+
+        let table = calibrate_microprice(&books, &MicropriceConfig::new(dec!(1))).unwrap();
+        assert_eq!(table.samples, 39);
+        assert_eq!(table.adjustments.len(), 2);
+
+        let bid_heavy = book(dec!(100.5), dec!(17), dec!(3));
+        let ask_heavy = book(dec!(100.5), dec!(3), dec!(17));
+        let balanced = book(dec!(100.5), dec!(5), dec!(5));
+        let close = |a: Option<Decimal>, b: Decimal| (a.unwrap() - b).abs() < dec!(0.000001);
+        assert!(close(table.adjustment(&bid_heavy), dec!(1)));
+        assert!(close(table.adjustment(&ask_heavy), dec!(-1)));
+        assert!(close(table.adjustment(&balanced), dec!(0)));
+        assert!(close(table.microprice(&bid_heavy), dec!(101.5)));
+
+        // A three-tick spread is outside the table
+        let mut wide = balanced.clone();
+        wide.asks[0].price += dec!(2);
+        assert!(table.adjustment(&wide).is_none());
+
+        let json = serde_json::to_string(&table).unwrap();
+        let restored: MicropriceTable = serde_json::from_str(&json).unwrap();
+        assert_eq!(restored, table);
+
+        assert!(calibrate_microprice(&books[..1], &MicropriceConfig::new(dec!(1))).is_none());
+    }
+
+    #[test]
+    fn test_depth_weighted_mid() {
+        let mut ob = book(dec!(100.5), dec!(3), dec!(1));
+        assert_eq!(depth_weighted_mid(&ob, 1), weighted_mid_price(&ob));
+
+        ob.bids.push(Level {
+            price: dec!(99),
+            quantity: dec!(1),
+        });
+        ob.asks.push(Level {
+            price: dec!(102),
+            quantity: dec!(3),
+        });
+        // Bid average 99.75 over 4, ask average 101.75 over 4
+        assert_eq!(depth_weighted_mid(&ob, 2), Some(dec!(100.75)));
+        ob.asks.clear();
+        assert!(depth_weighted_mid(&ob, 2).is_none());
+    }
+}
@@ -1,17 +1,54 @@
 //! Metrics Calculation Module
 //!
 //! This module provides advanced metrics calculations for market microstructure analysis.
+//! [`FootprintBar`]s break bars down into bid/ask volume at each price, and
+//! [`TpoProfile`]s build time-based market profiles. [`calculate_vpin`]
+//! measures flow toxicity from bulk-classified volume, and
+//! [`decompose_spreads`] splits the effective spread into realized spread
+//! and price impact. Roll, Corwin-Schultz, Amihud and Kyle estimators
+//! measure liquidity from the tape alone, and the volatility estimators
+//! handle microstructure noise in high-frequency prices.
+//! [`calibrate_microprice`] fits Stoikov's microprice from snapshot history.
+
+mod footprint;
+mod liquidity;
+mod microprice;
+mod spreads;
+mod tpo;
+mod volatility;
+mod vpin;
+
+pub use footprint::{
+    build_footprints, FootprintBar, FootprintBuilder, FootprintConfig, FootprintLevel,
+    StackedImbalance,
+};
+pub use liquidity::{
+    amihud_illiquidity, corwin_schultz_spread, kyle_lambda, roll_spread, LiquidityEstimate,
+};
+pub use microprice::{calibrate_microprice, depth_weighted_mid, MicropriceConfig, MicropriceTable};
+pub use spreads::{
+    decompose_spreads, summarize_spreads, HorizonSpread, HorizonSummary, SpreadSummary, TradeSpread,
+};
+pub use tpo::{build_tpo_profile, tpo_letter, ProfileShape, TpoConfig, TpoLevel, TpoProfile};
+pub use volatility::{
+    bipower_variation, garman_klass_variance, mid_prices, parkinson_variance, realized_kernel,
+    realized_variance, rogers_satchell_variance, signature_plot, trade_prices,
+    two_scale_realized_variance, MAX_GRID_POINTS,
+};
+pub use vpin::{
+    bulk_volume_classification, calculate_vpin, BvcDistribution, ClassifiedVolume, VpinConfig,
+};
 
 use crate::types::{OrderBook, Trade};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 /// Volume Profile data structure
 #[derive(Debug, Clone)]
 pub struct VolumeProfile {
-    /// Price levels and their volumes
-    pub levels: HashMap<Decimal, Decimal>,
+    /// Price levels and their volumes, sorted by price
+    pub levels: BTreeMap<Decimal, Decimal>,
     /// Point of Control (price with highest volume)
     pub poc: Option<Decimal>,
     /// Value Area High
@@ -20,7 +57,42 @@ pub struct VolumeProfile {
     pub val: Option<Decimal>,
 }
 
-/// Calculate volume profile from trades
+/// Round a price to the nearest multiple of `tick_size`
+///
+/// This is the bucketing used by [`calculate_volume_profile`] and the
+/// footprint builder.
+///
+/// # Panics
+/// If `tick_size` is zero
+#[must_use]
+pub fn price_bucket(price: Decimal, tick_size: Decimal) -> Decimal {
+    (price / tick_size).round() * tick_size
+}
+
+/// Find the Point of Control of a price/volume ladder
+///
+/// Ties are broken by distance to the middle of the price range, then by
+/// the lower price, so the result does not depend on input order.
+pub(crate) fn point_of_control<I>(levels: I) -> Option<Decimal>
+where
+    I: IntoIterator<Item = (Decimal, Decimal)>,
+    I::IntoIter: Clone,
+{
+    let levels = levels.into_iter();
+    let low = levels.clone().map(|(p, _)| p).min()?;
+    let high = levels.clone().map(|(p, _)| p).max()?;
+    let center = (low + high) / dec!(2);
+
+    levels
+        .min_by(|(pa, va), (pb, vb)| {
+            vb.cmp(va)
+                .then_with(|| (*pa - center).abs().cmp(&(*pb - center).abs()))
+                .then_with(|| pa.cmp(pb))
+        })
+        .map(|(price, _)| price)
+}
+
+/// Calculate volume profile from trades with a 70% value area
 ///
 /// # Arguments
 /// * `trades` - List of executed trades
@@ -30,87 +102,107 @@ pub struct VolumeProfile {
 /// VolumeProfile structure with POC, VAH, VAL
 #[must_use]
 pub fn calculate_volume_profile(trades: &[Trade], tick_size: Decimal) -> VolumeProfile {
-    let mut levels: HashMap<Decimal, Decimal> = HashMap::new();
+    calculate_volume_profile_with_value_area(trades, tick_size, dec!(0.70))
+}
+
+/// Calculate volume profile from trades with a configurable value area
+///
+/// The value area starts at the POC and expands outward: at each step the
+/// combined volume of the next two levels above is compared with the next
+/// two levels below, and the larger pair is added (both on a tie) until
+/// `value_area` of the total volume is covered. The result is always a
+/// contiguous range of levels.
+///
+/// # Arguments
+/// * `trades` - List of executed trades
+/// * `tick_size` - Price tick size for grouping
+/// * `value_area` - Fraction of total volume in the value area (e.g. 0.70)
+///
+/// # Returns
+/// VolumeProfile structure with POC, VAH, VAL
+#[must_use]
+pub fn calculate_volume_profile_with_value_area(
+    trades: &[Trade],
+    tick_size: Decimal,
+    value_area: Decimal,
+) -> VolumeProfile {
+    let mut levels: BTreeMap<Decimal, Decimal> = BTreeMap::new();
 
     // Group trades by price level
     for trade in trades {
-        let price_level = (trade.price / tick_size).round() * tick_size;
+        let price_level = price_bucket(trade.price, tick_size);
         *levels.entry(price_level).or_insert(dec!(0)) += trade.quantity;
     }
 
-    if levels.is_empty() {
+    let Some(poc) = point_of_control(levels.iter().map(|(&p, &v)| (p, v))) else {
         return VolumeProfile {
             levels,
             poc: None,
             vah: None,
             val: None,
         };
-    }
-
-    // Find Point of Control (highest volume)
-    let poc = levels
-        .iter()
-        .max_by_key(|(_, &vol)| vol)
-        .map(|(&price, _)| price);
-
-    // Calculate Value Area (70% of volume)
-    let total_volume: Decimal = levels.values().sum();
-    let value_area_volume = total_volume * dec!(0.70);
-
-    // Sort levels by volume descending to build value area from highest-volume levels
-    let mut sorted_by_volume: Vec<_> = levels.iter().collect();
-    sorted_by_volume.sort_by(|a, b| b.1.cmp(a.1));
-
-    // Find VAH and VAL by accumulating highest-volume price levels until 70% is reached
-    let (vah, val) = if let Some(poc_price) = poc {
-        let mut accumulated = dec!(0);
-        let mut low = poc_price;
-        let mut high = poc_price;
-
-        for (&price, &volume) in &sorted_by_volume {
-            if accumulated >= value_area_volume {
-                break;
-            }
-            accumulated += volume;
-            if price > high {
-                high = price;
-            }
-            if price < low {
-                low = price;
-            }
-        }
-        (Some(high), Some(low))
-    } else {
-        (None, None)
     };
 
+    let ladder: Vec<(Decimal, Decimal)> = levels.iter().map(|(&p, &v)| (p, v)).collect();
+    let (val, vah) = value_area_bounds(&ladder, poc, value_area);
+
     VolumeProfile {
         levels,
-        poc,
-        vah,
-        val,
+        poc: Some(poc),
+        vah: Some(vah),
+        val: Some(val),
     }
 }
 
+/// Expand a value area outward from `poc` over a price-ascending ladder
+///
+/// See [`calculate_volume_profile_with_value_area`] for the rule.
+///
+/// # Returns
+/// (value area low, value area high)
+pub(crate) fn value_area_bounds(
+    ladder: &[(Decimal, Decimal)],
+    poc: Decimal,
+    value_area: Decimal,
+) -> (Decimal, Decimal) {
+    let total_volume: Decimal = ladder.iter().map(|(_, v)| v).sum();
+    let target = total_volume * value_area;
+
+    // Indices of the lowest and highest levels inside the value area
+    let poc_index = ladder.iter().position(|&(p, _)| p == poc).unwrap_or(0);
+    let (mut low, mut high) = (poc_index, poc_index);
+    let mut accumulated = ladder[poc_index].1;
+
+    while accumulated < target && (low > 0 || high + 1 < ladder.len()) {
+        let above: Decimal = ladder[high + 1..].iter().take(2).map(|(_, v)| v).sum();
+        let below: Decimal = ladder[..low].iter().rev().take(2).map(|(_, v)| v).sum();
+        let above_count = (ladder.len() - 1 - high).min(2);
+        let below_count = low.min(2);
+
+        if above_count > 0 && (below_count == 0 || above >= below) {
+            high += above_count;
+            accumulated += above;
+        }
+        if below_count > 0 && (above_count == 0 || below >= above) {
+            low -= below_count;
+            accumulated += below;
+        }
+    }
+
+    (ladder[low].0, ladder[high].0)
+}
+
 /// Calculate Delta Volume (buying pressure - selling pressure)
 ///
 /// # Arguments
 /// * `trades` - List of executed trades
 ///
 /// # Returns
-/// Net delta (positive = buying pressure, negative = selling pressure)
+/// Net delta (positive = buying pressure, negative = selling pressure).
+/// Trades with an unknown aggressor do not contribute.
 #[must_use]
 pub fn calculate_delta(trades: &[Trade]) -> Decimal {
-    trades
-        .iter()
-        .map(|trade| {
-            if trade.side == "buy" {
-                trade.quantity
-            } else {
-                -trade.quantity
-            }
-        })
-        .sum()
+    trades.iter().map(Trade::signed_quantity).sum()
 }
 
 /// Calculate Cumulative Volume Delta over time
@@ -119,18 +211,15 @@ pub fn calculate_delta(trades: &[Trade]) -> Decimal {
 /// * `trades` - List of executed trades (should be sorted by timestamp)
 ///
 /// # Returns
-/// Vector of (timestamp, cumulative_delta) pairs
+/// Vector of (timestamp, cumulative_delta) pairs. Trades with an unknown
+/// aggressor leave the running total unchanged.
 #[must_use]
 pub fn calculate_cvd(trades: &[Trade]) -> Vec<(i64, Decimal)> {
     let mut cvd = dec!(0);
     let mut result = Vec::new();
 
     for trade in trades {
-        if trade.side == "buy" {
-            cvd += trade.quantity;
-        } else {
-            cvd -= trade.quantity;
-        }
+        cvd += trade.signed_quantity();
         result.push((trade.timestamp, cvd));
     }
 
@@ -163,32 +252,32 @@ pub fn weighted_mid_price(orderbook: &OrderBook) -> Option<Decimal> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Level;
+    use crate::types::{Level, Side};
 
     fn sample_trades() -> Vec<Trade> {
         vec![
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1000,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.5),
-                side: "sell".to_string(),
+                side: Side::Sell,
                 timestamp: 1001,
             },
             Trade {
                 price: dec!(50001.0),
                 quantity: dec!(2.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1002,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.5),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1003,
             },
         ]
@@ -227,6 +316,82 @@ mod tests {
         assert_eq!(profile.poc.unwrap(), dec!(50000.0));
     }
 
+    fn profile_trades(volumes: &[(Decimal, Decimal)]) -> Vec<Trade> {
+        volumes
+            .iter()
+            .map(|&(price, quantity)| Trade {
+                price,
+                quantity,
+                side: Side::Buy,
+                timestamp: 0,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_value_area_expands_from_poc() {
+        let trades = profile_trades(&[
+            (dec!(96), dec!(1)),
+            (dec!(97), dec!(8)),
+            (dec!(98), dec!(1)),
+            (dec!(99), dec!(2)),
+            (dec!(100), dec!(10)),
+            (dec!(101), dec!(3)),
+            (dec!(102), dec!(2)),
+            (dec!(103), dec!(1)),
+            (dec!(104), dec!(1)),
+        ]);
+        let profile = calculate_volume_profile(&trades, dec!(1));
+
+        // 100 -> +101/102 (5 vs 3) -> +99/98 (3 vs 2) -> +97/96 (9 vs 2)
+        assert_eq!(profile.poc, Some(dec!(100)));
+        assert_eq!(profile.vah, Some(dec!(102)));
+        assert_eq!(profile.val, Some(dec!(96)));
+
+        let full = calculate_volume_profile_with_value_area(&trades, dec!(1), dec!(1));
+        assert_eq!((full.val, full.vah), (Some(dec!(96)), Some(dec!(104))));
+
+        let poc_only = calculate_volume_profile_with_value_area(&trades, dec!(1), dec!(0.3));
+        assert_eq!(
+            (poc_only.val, poc_only.vah),
+            (Some(dec!(100)), Some(dec!(100)))
+        );
+    }
+
+    #[test]
+    fn test_poc_tie_breaking_is_deterministic() {
+        let mut trades = profile_trades(&[
+            (dec!(100), dec!(5)),
+            (dec!(101), dec!(5)),
+            (dec!(105), dec!(5)),
+        ]);
+        // Closest to the middle of the range (102.5)
+        assert_eq!(
+            calculate_volume_profile(&trades, dec!(1)).poc,
+            Some(dec!(101))
+        );
+
+        trades.truncate(1);
+        trades.push(profile_trades(&[(dec!(102), dec!(5))])[0].clone());
+        for _ in 0..10 {
+            trades.reverse();
+            // Equidistant from 101: lower price wins
+            assert_eq!(
+                calculate_volume_profile(&trades, dec!(1)).poc,
+                Some(dec!(100))
+            );
+        }
+    }
+
+    #[test]
+    fn test_volume_profile_levels_sorted() {
+        let trades = sample_trades();
+        let profile = calculate_volume_profile(&trades, dec!(1.0));
+        let prices: Vec<_> = profile.levels.keys().copied().collect();
+
+        assert_eq!(prices, vec![dec!(50000), dec!(50001)]);
+    }
+
     #[test]
     fn test_weighted_mid_price() {
         let orderbook = OrderBook {
@@ -245,4 +410,19 @@ mod tests {
         // (100 * 5 + 101 * 10) / 15 = (500 + 1010) / 15 = 1510 / 15 = 100.666...
         assert!(wmp > dec!(100.6) && wmp < dec!(100.7));
     }
+
+    #[test]
+    fn test_unknown_side_ignored_in_delta() {
+        let mut trades = sample_trades();
+        trades.push(Trade {
+            price: dec!(50000.0),
+            quantity: dec!(3.0),
+            side: Side::Unknown,
+            timestamp: 1004,
+        });
+
+        assert_eq!(calculate_delta(&trades), dec!(4.0));
+        let cvd = calculate_cvd(&trades);
+        assert_eq!(cvd[4], (1004, dec!(4.0)));
+    }
 }
@@ -0,0 +1,273 @@
+//! Spread Decomposition for Transaction Cost Analysis
+//!
+//! Joins trades with the prevailing book snapshots. For a trade at price `p`
+//! with direction `d` (+1 buy, -1 sell), mid `m0` when it printed and mid
+//! `m1` a horizon later:
+//!
+//! - effective spread = `2 d (p - m0)`
+//! - realized spread = `2 d (p - m1)`
+//! - price impact = `2 d (m1 - m0)`
+//!
+//! so the effective spread is the realized spread plus the price impact.
+
+use crate::orderbook::{mid_price, prevailing_book};
+use crate::types::{OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Realized spread and price impact at one horizon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HorizonSpread {
+    /// Horizon in the trades' timestamp unit
+    pub horizon: i64,
+    /// Mid a horizon after the trade, None when the books end earlier
+    pub future_mid: Option<Decimal>,
+    pub realized: Option<Decimal>,
+    pub price_impact: Option<Decimal>,
+}
+
+/// Spread decomposition of one trade
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TradeSpread {
+    /// Index of the trade in the input slice
+    pub index: usize,
+    pub timestamp: i64,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub side: Side,
+    /// Prevailing mid when the trade printed
+    pub mid: Decimal,
+    pub effective: Decimal,
+    pub horizons: Vec<HorizonSpread>,
+}
+
+/// Volume-weighted averages at one horizon
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HorizonSummary {
+    pub horizon: i64,
+    pub realized: Decimal,
+    pub price_impact: Decimal,
+    /// Trades with a future mid at this horizon
+    pub trades: usize,
+}
+
+/// Volume-weighted spread averages
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpreadSummary {
+    pub trades: usize,
+    pub volume: Decimal,
+    pub effective: Decimal,
+    pub horizons: Vec<HorizonSummary>,
+}
+
+/// Decompose the spread paid by each trade
+///
+/// Trades with an unknown side or without a prevailing two-sided quote are
+/// skipped; sign them first with [`crate::tape::infer_sides`] if needed.
+///
+/// # Arguments
+/// * `trades` - Executed trades
+/// * `books` - Snapshots sorted by timestamp
+/// * `horizons` - Horizons for the realized spread (e.g. 1, 5 and 60 seconds)
+///
+/// # Returns
+/// One TradeSpread per decomposable trade
+#[must_use]
+pub fn decompose_spreads(
+    trades: &[Trade],
+    books: &[OrderBook],
+    horizons: &[i64],
+) -> Vec<TradeSpread> {
+    let last_book = books.last().map_or(i64::MIN, |b| b.timestamp);
+
+    trades
+        .iter()
+        .enumerate()
+        .filter_map(|(index, trade)| {
+            let direction = match trade.side {
+                Side::Buy => dec!(1),
+                Side::Sell => dec!(-1),
+                Side::Unknown => return None,
+            };
+            let mid = prevailing_book(books, trade.timestamp).and_then(mid_price)?;
+
+            let horizons = horizons
+                .iter()
+                .map(|&horizon| {
+                    let at = trade.timestamp + horizon;
+                    let future_mid = (at <= last_book)
+                        .then(|| prevailing_book(books, at).and_then(mid_price))
+                        .flatten();
+                    HorizonSpread {
+                        horizon,
+                        future_mid,
+                        realized: future_mid.map(|m| dec!(2) * direction * (trade.price - m)),
+                        price_impact: future_mid.map(|m| dec!(2) * direction * (m - mid)),
+                    }
+                })
+                .collect();
+
+            Some(TradeSpread {
+                index,
+                timestamp: trade.timestamp,
+                price: trade.price,
+                quantity: trade.quantity,
+                side: trade.side,
+                mid,
+                effective: dec!(2) * direction * (trade.price - mid),
+                horizons,
+            })
+        })
+        .collect()
+}
+
+/// Volume-weighted averages of a spread decomposition
+///
+/// # Arguments
+/// * `spreads` - Output of [`decompose_spreads`]
+///
+/// # Returns
+/// SpreadSummary, or None when there is no volume. Horizon averages only
+/// use trades with a future mid; horizons without any average to zero.
+#[must_use]
+pub fn summarize_spreads(spreads: &[TradeSpread]) -> Option<SpreadSummary> {
+    let volume: Decimal = spreads.iter().map(|s| s.quantity).sum();
+    if volume <= dec!(0) {
+        return None;
+    }
+    let effective = spreads
+        .iter()
+        .map(|s| s.effective * s.quantity)
+        .sum::<Decimal>()
+        / volume;
+
+    let horizon_count = spreads.first().map_or(0, |s| s.horizons.len());
+    let horizons = (0..horizon_count)
+        .map(|h| {
+            let mut weight = dec!(0);
+            let (mut realized, mut price_impact) = (dec!(0), dec!(0));
+            let mut trades = 0;
+            for s in spreads {
+                if let Some(HorizonSpread {
+                    realized: Some(r),
+                    price_impact: Some(i),
+                    ..
+                }) = s.horizons.get(h)
+                {
+                    weight += s.quantity;
+                    realized += r * s.quantity;
+                    price_impact += i * s.quantity;
+                    trades += 1;
+                }
+            }
+            let average = |total: Decimal| {
+                if weight > dec!(0) {
+                    total / weight
+                } else {
+                    dec!(0)
+                }
+            };
+            HorizonSummary {
+                horizon: spreads[0].horizons[h].horizon,
+                realized: average(realized),
+                price_impact: average(price_impact),
+                trades,
+            }
+        })
+        .collect();
+
+    Some(SpreadSummary {
+        trades: spreads.len(),
+        volume,
+        effective,
+        horizons,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Level;
+
+    fn book(bid: Decimal, ask: Decimal, timestamp: i64) -> OrderBook {
+        OrderBook {
+            bids: vec![Level {
+                price: bid,
+                quantity: dec!(1),
+            }],
+            asks: vec![Level {
+                price: ask,
+                quantity: dec!(1),
+            }],
+            timestamp,
+        }
+    }
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    fn books() -> Vec<OrderBook> {
+        vec![
+            book(dec!(99), dec!(101), 0),
+            book(dec!(100), dec!(102), 5),
+            book(dec!(99), dec!(101), 10),
+        ]
+    }
+
+    #[test]
+    fn test_decompose_spreads() {
+        let trades = vec![
+            trade(dec!(101), dec!(1), Side::Buy, 1),
+            trade(dec!(100), dec!(3), Side::Unknown, 2),
+            trade(dec!(99), dec!(2), Side::Sell, 3),
+        ];
+        let spreads = decompose_spreads(&trades, &books(), &[5, 20]);
+
+        assert_eq!(spreads.len(), 2);
+        let buy = &spreads[0];
+        assert_eq!(buy.index, 0);
+        assert_eq!(buy.effective, dec!(2));
+
+        // Mid moves from 100 to 101 five seconds later
+        let h = &buy.horizons[0];
+        assert_eq!(h.future_mid, Some(dec!(101)));
+        assert_eq!(h.realized, Some(dec!(0)));
+        assert_eq!(h.price_impact, Some(dec!(2)));
+        assert_eq!(h.realized.unwrap() + h.price_impact.unwrap(), buy.effective);
+        // The books end before the 20 second horizon
+        assert_eq!(buy.horizons[1].future_mid, None);
+
+        // The sell's mid rose after it: negative impact, positive realized
+        let sell = &spreads[1];
+        assert_eq!(sell.index, 2);
+        assert_eq!(sell.horizons[0].price_impact, Some(dec!(-2)));
+        assert_eq!(sell.horizons[0].realized, Some(dec!(4)));
+    }
+
+    #[test]
+    fn test_summarize_spreads() {
+        let trades = vec![
+            trade(dec!(101), dec!(1), Side::Buy, 1),
+            trade(dec!(99), dec!(3), Side::Sell, 3),
+        ];
+        let spreads = decompose_spreads(&trades, &books(), &[5, 20]);
+        let summary = summarize_spreads(&spreads).unwrap();
+
+        assert_eq!(summary.trades, 2);
+        assert_eq!(summary.volume, dec!(4));
+        assert_eq!(summary.effective, dec!(2));
+        // (1 * 2 + 3 * -2) / 4
+        assert_eq!(summary.horizons[0].price_impact, dec!(-1));
+        assert_eq!(summary.horizons[0].realized, dec!(3));
+        assert_eq!(summary.horizons[1].trades, 0);
+
+        assert!(summarize_spreads(&[]).is_none());
+    }
+}
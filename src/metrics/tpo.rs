@@ -0,0 +1,419 @@
+//! Market Profile (Time-Price Opportunity) Charts
+//!
+//! Each time period of the session gets a letter (`A`-`Z`, then `a`-`z`),
+//! which is printed at every price between that period's low and high. The
+//! number of letters at a price is its TPO count, and the POC and value area
+//! are computed on those counts the same way the volume profile computes
+//! them on volume.
+
+use super::{point_of_control, price_bucket, value_area_bounds};
+use crate::types::Trade;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Market profile settings
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TpoConfig {
+    /// Price bucket size
+    pub tick_size: Decimal,
+    /// Period length in the trades' timestamp unit
+    pub period: i64,
+    /// Number of leading periods forming the initial balance
+    pub initial_balance_periods: usize,
+    /// Fraction of TPOs inside the value area
+    pub value_area: Decimal,
+    /// First period start; defaults to the first trade's timestamp
+    /// aligned down to a multiple of `period`. Earlier trades are ignored.
+    pub session_start: Option<i64>,
+}
+
+impl Default for TpoConfig {
+    fn default() -> Self {
+        Self {
+            tick_size: dec!(1),
+            period: 1800,
+            initial_balance_periods: 2,
+            value_area: dec!(0.70),
+            session_start: None,
+        }
+    }
+}
+
+/// Overall shape of a profile
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ProfileShape {
+    /// Balanced, bell-like distribution with the POC near the middle
+    #[default]
+    Normal,
+    /// TPOs concentrated in the upper part (short covering)
+    PShape,
+    /// TPOs concentrated in the lower part (long liquidation)
+    BShape,
+    /// Thin, elongated profile with few TPOs per price
+    Trend,
+    /// Two balance areas separated by single prints
+    DoubleDistribution,
+}
+
+/// The letters printed at one price
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TpoLevel {
+    pub price: Decimal,
+    /// Period letters in chronological order
+    pub letters: String,
+    pub tpos: usize,
+}
+
+/// A session's market profile
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TpoProfile {
+    /// Start of the first period
+    pub start: i64,
+    pub period: i64,
+    /// Number of periods from the first to the last trade
+    pub periods: usize,
+    /// Levels sorted by price, highest first
+    pub levels: Vec<TpoLevel>,
+    /// Price with the most TPOs
+    pub poc: Option<Decimal>,
+    /// Value Area High
+    pub vah: Option<Decimal>,
+    /// Value Area Low
+    pub val: Option<Decimal>,
+    /// (low, high) of the initial balance periods
+    pub initial_balance: Option<(Decimal, Decimal)>,
+    /// Single-TPO prices inside the profile, excluding the tails at
+    /// either extreme
+    pub single_prints: Vec<Decimal>,
+    /// The high has two or more TPOs (no excess)
+    pub poor_high: bool,
+    /// The low has two or more TPOs (no excess)
+    pub poor_low: bool,
+    pub shape: ProfileShape,
+}
+
+/// Letter for a period index, cycling through `A`-`Z` then `a`-`z`
+#[must_use]
+pub fn tpo_letter(period: usize) -> char {
+    let index = (period % 52) as u8;
+    if index < 26 {
+        (b'A' + index) as char
+    } else {
+        (b'a' + index - 26) as char
+    }
+}
+
+/// Build a market profile from one session's trades
+///
+/// # Arguments
+/// * `trades` - Executed trades, sorted by timestamp
+/// * `config` - Tick size, period length and value area settings
+///
+/// # Returns
+/// TpoProfile with POC, value area, initial balance, single prints,
+/// poor highs/lows and shape (empty when there are no trades or
+/// `tick_size` is not positive)
+#[must_use]
+pub fn build_tpo_profile(trades: &[Trade], config: &TpoConfig) -> TpoProfile {
+    let period = config.period.max(1);
+    let Some(first) = trades.first().filter(|_| config.tick_size > dec!(0)) else {
+        return TpoProfile {
+            period,
+            ..TpoProfile::default()
+        };
+    };
+    let start = config
+        .session_start
+        .unwrap_or_else(|| first.timestamp.div_euclid(period) * period);
+
+    // Price range of each period that traded
+    let mut ranges: BTreeMap<usize, (Decimal, Decimal)> = BTreeMap::new();
+    for trade in trades.iter().filter(|t| t.timestamp >= start) {
+        let index = ((trade.timestamp - start) / period) as usize;
+        let price = price_bucket(trade.price, config.tick_size);
+        ranges
+            .entry(index)
+            .and_modify(|(low, high)| {
+                *low = (*low).min(price);
+                *high = (*high).max(price);
+            })
+            .or_insert((price, price));
+    }
+
+    let step = config.tick_size;
+    let mut letters: BTreeMap<Decimal, String> = BTreeMap::new();
+    for (&index, &(low, high)) in &ranges {
+        let mut price = low;
+        while price <= high {
+            letters.entry(price).or_default().push(tpo_letter(index));
+            price += step;
+        }
+    }
+
+    let ladder: Vec<(Decimal, Decimal)> = letters
+        .iter()
+        .map(|(&p, l)| (p, Decimal::from(l.chars().count())))
+        .collect();
+    let poc = point_of_control(ladder.iter().copied());
+    let (val, vah) = match poc {
+        Some(poc) => {
+            let (val, vah) = value_area_bounds(&ladder, poc, config.value_area);
+            (Some(val), Some(vah))
+        }
+        None => (None, None),
+    };
+
+    let initial_balance = ranges
+        .range(..config.initial_balance_periods)
+        .map(|(_, &range)| range)
+        .reduce(|(l1, h1), (l2, h2)| (l1.min(l2), h1.max(h2)));
+
+    let counts: Vec<(Decimal, usize)> = letters
+        .iter()
+        .map(|(&p, l)| (p, l.chars().count()))
+        .collect();
+    let single_prints = single_prints(&counts);
+    let poor_low = counts.first().is_some_and(|&(_, n)| n >= 2);
+    let poor_high = counts.last().is_some_and(|&(_, n)| n >= 2);
+    let shape = classify_shape(&counts, poc, &single_prints);
+
+    let periods = ranges.keys().next_back().map_or(0, |last| last + 1);
+    let levels = letters
+        .into_iter()
+        .rev()
+        .map(|(price, letters)| TpoLevel {
+            price,
+            tpos: letters.chars().count(),
+            letters,
+        })
+        .collect();
+
+    TpoProfile {
+        start,
+        period,
+        periods,
+        levels,
+        poc,
+        vah,
+        val,
+        initial_balance,
+        single_prints,
+        poor_high,
+        poor_low,
+        shape,
+    }
+}
+
+/// Single-TPO prices between the lowest and highest multi-TPO prices
+fn single_prints(ascending: &[(Decimal, usize)]) -> Vec<Decimal> {
+    let first = ascending.iter().position(|&(_, n)| n >= 2);
+    let last = ascending.iter().rposition(|&(_, n)| n >= 2);
+    match (first, last) {
+        (Some(first), Some(last)) => ascending[first..=last]
+            .iter()
+            .filter(|&&(_, n)| n == 1)
+            .map(|&(p, _)| p)
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Classify the profile shape
+///
+/// Single prints inside the profile make a double distribution, fewer than
+/// 1.5 TPOs per price on average a trend profile, and a POC in the upper or
+/// lower third a P or b shape.
+fn classify_shape(
+    ascending: &[(Decimal, usize)],
+    poc: Option<Decimal>,
+    single_prints: &[Decimal],
+) -> ProfileShape {
+    let (Some(&(low, _)), Some(&(high, _)), Some(poc)) = (ascending.first(), ascending.last(), poc)
+    else {
+        return ProfileShape::Normal;
+    };
+    if !single_prints.is_empty() {
+        return ProfileShape::DoubleDistribution;
+    }
+
+    let total: usize = ascending.iter().map(|&(_, n)| n).sum();
+    if ascending.len() >= 3 && Decimal::from(total) < Decimal::from(ascending.len()) * dec!(1.5) {
+        return ProfileShape::Trend;
+    }
+
+    let range = high - low;
+    if range.is_zero() {
+        return ProfileShape::Normal;
+    }
+    let position = (poc - low) / range;
+    if position > dec!(2) / dec!(3) {
+        ProfileShape::PShape
+    } else if position < dec!(1) / dec!(3) {
+        ProfileShape::BShape
+    } else {
+        ProfileShape::Normal
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+
+    fn trade(price: Decimal, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity: dec!(1),
+            side: Side::Unknown,
+            timestamp,
+        }
+    }
+
+    fn config() -> TpoConfig {
+        TpoConfig {
+            period: 30,
+            ..TpoConfig::default()
+        }
+    }
+
+    /// Periods A-E trading 100-102, 99-103, 100-101, 101-102, 100-101
+    fn balanced_session() -> Vec<Trade> {
+        vec![
+            trade(dec!(100), 0),
+            trade(dec!(102), 10),
+            trade(dec!(99), 30),
+            trade(dec!(103), 50),
+            trade(dec!(100), 60),
+            trade(dec!(101), 70),
+            trade(dec!(102), 90),
+            trade(dec!(101), 100),
+            trade(dec!(100), 120),
+            trade(dec!(101), 149),
+        ]
+    }
+
+    #[test]
+    fn test_tpo_letters() {
+        let profile = build_tpo_profile(&balanced_session(), &config());
+
+        assert_eq!(profile.periods, 5);
+        let rows: Vec<_> = profile
+            .levels
+            .iter()
+            .map(|l| (l.price, l.letters.as_str()))
+            .collect();
+        assert_eq!(
+            rows,
+            vec![
+                (dec!(103), "B"),
+                (dec!(102), "ABD"),
+                (dec!(101), "ABCDE"),
+                (dec!(100), "ABCE"),
+                (dec!(99), "B"),
+            ]
+        );
+        assert_eq!(profile.initial_balance, Some((dec!(99), dec!(103))));
+        assert_eq!(tpo_letter(26), 'a');
+        assert_eq!(tpo_letter(52), 'A');
+    }
+
+    #[test]
+    fn test_tpo_value_area() {
+        let profile = build_tpo_profile(&balanced_session(), &config());
+
+        // 14 TPOs, 70% = 9.8: POC 101 (5), then 100+99 (5) beats 102+103 (4)
+        assert_eq!(profile.poc, Some(dec!(101)));
+        assert_eq!(profile.val, Some(dec!(99)));
+        assert_eq!(profile.vah, Some(dec!(101)));
+        assert_eq!(profile.shape, ProfileShape::Normal);
+        assert!(!profile.poor_high);
+        assert!(!profile.poor_low);
+        assert!(profile.single_prints.is_empty());
+    }
+
+    #[test]
+    fn test_single_prints_and_poor_extremes() {
+        // A and B balance at 100-101, C runs up through 102-103 in one
+        // period, D and E balance at 104-105
+        let trades = vec![
+            trade(dec!(100), 0),
+            trade(dec!(101), 10),
+            trade(dec!(100), 30),
+            trade(dec!(101), 40),
+            trade(dec!(101), 60),
+            trade(dec!(104), 80),
+            trade(dec!(104), 90),
+            trade(dec!(105), 100),
+            trade(dec!(104), 120),
+            trade(dec!(105), 130),
+        ];
+        let profile = build_tpo_profile(&trades, &config());
+
+        assert_eq!(profile.single_prints, vec![dec!(102), dec!(103)]);
+        assert_eq!(profile.shape, ProfileShape::DoubleDistribution);
+        assert!(profile.poor_high);
+        assert!(profile.poor_low);
+    }
+
+    #[test]
+    fn test_trend_and_p_shape() {
+        // Each period trades two new prices higher
+        let trend: Vec<Trade> = (0..5)
+            .flat_map(|i| {
+                [
+                    trade(Decimal::from(100 + i * 2), i * 30),
+                    trade(Decimal::from(101 + i * 2), i * 30 + 10),
+                ]
+            })
+            .collect();
+        let profile = build_tpo_profile(&trend, &config());
+        assert_eq!(profile.shape, ProfileShape::Trend);
+
+        // Early rally from 100, then four periods holding 104-105
+        let mut p_shape = vec![trade(dec!(100), 0), trade(dec!(105), 10)];
+        p_shape
+            .extend((1..5).flat_map(|i| [trade(dec!(104), i * 30), trade(dec!(105), i * 30 + 5)]));
+        let profile = build_tpo_profile(&p_shape, &config());
+        assert_eq!(profile.shape, ProfileShape::PShape);
+        assert!(profile.poor_high);
+        assert!(!profile.poor_low);
+    }
+
+    #[test]
+    fn test_empty_session() {
+        let profile = build_tpo_profile(&[], &config());
+        assert!(profile.levels.is_empty());
+        assert_eq!(profile.poc, None);
+        assert_eq!(profile.periods, 0);
+
+        let zero_tick = TpoConfig {
+            tick_size: dec!(0),
+            ..config()
+        };
+        let profile = build_tpo_profile(&[trade(dec!(100), 0)], &zero_tick);
+        assert!(profile.levels.is_empty());
+        assert_eq!(profile.poc, None);
+    }
+
+    #[test]
+    fn test_trades_before_session_start_are_skipped() {
+        let config = TpoConfig {
+            session_start: Some(100),
+            ..config()
+        };
+        let trades = vec![
+            trade(dec!(90), 50),
+            trade(dec!(100), 100),
+            trade(dec!(101), 140),
+        ];
+        let profile = build_tpo_profile(&trades, &config);
+
+        assert_eq!(profile.start, 100);
+        assert_eq!(profile.periods, 2);
+        let prices: Vec<Decimal> = profile.levels.iter().map(|l| l.price).collect();
+        assert_eq!(prices, vec![dec!(101), dec!(100)]);
+        assert_eq!(profile.levels[1].letters, "A");
+    }
+}
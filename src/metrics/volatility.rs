@@ -0,0 +1,378 @@
+//! Realized Volatility Estimators
+//!
+//! Estimators work on a `(timestamp, price)` series built from trades with
+//! [`trade_prices`] or from book snapshots with [`mid_prices`], or on bars
+//! for the range-based estimators. All results are variances of log returns
+//! over the whole series (or per bar for the range estimators); take the
+//! square root for volatility. Intervals whose grid would exceed
+//! [`MAX_GRID_POINTS`] give no estimate.
+
+use crate::bars::Bar;
+use crate::orderbook::mid_price;
+use crate::types::{OrderBook, Trade};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use std::f64::consts::{LN_2, PI};
+
+/// Largest sampling grid built from one series
+///
+/// A day of nanosecond timestamps sampled every nanosecond would need
+/// 8.6e13 points; such intervals are rejected instead of allocated.
+pub const MAX_GRID_POINTS: usize = 10_000_000;
+
+/// Trade prices as a `(timestamp, price)` series
+#[must_use]
+pub fn trade_prices(trades: &[Trade]) -> Vec<(i64, Decimal)> {
+    trades.iter().map(|t| (t.timestamp, t.price)).collect()
+}
+
+/// Mid prices as a `(timestamp, price)` series, skipping one-sided books
+#[must_use]
+pub fn mid_prices(books: &[OrderBook]) -> Vec<(i64, Decimal)> {
+    books
+        .iter()
+        .filter_map(|b| Some((b.timestamp, mid_price(b)?)))
+        .collect()
+}
+
+/// Sample a series on a regular grid with previous-tick interpolation
+///
+/// The grid starts at the first timestamp and steps by `interval` up to the
+/// last timestamp; each point takes the last price at or before it.
+/// `interval <= 0` returns every price. Grids of more than
+/// [`MAX_GRID_POINTS`] points return nothing.
+fn sample(series: &[(i64, Decimal)], interval: i64) -> Vec<f64> {
+    let prices = |slice: &[(i64, Decimal)]| -> Vec<f64> {
+        slice.iter().filter_map(|(_, p)| p.to_f64()).collect()
+    };
+    let (Some(first), Some(last)) = (series.first(), series.last()) else {
+        return Vec::new();
+    };
+    if interval <= 0 {
+        return prices(series);
+    }
+
+    let points = (i128::from(last.0) - i128::from(first.0)) / i128::from(interval) + 1;
+    if points > MAX_GRID_POINTS as i128 {
+        return Vec::new();
+    }
+
+    let mut sampled = Vec::with_capacity(points.max(0) as usize);
+    let mut index = 0;
+    let mut at = Some(first.0);
+    while let Some(point) = at.filter(|&point| point <= last.0) {
+        while index + 1 < series.len() && series[index + 1].0 <= point {
+            index += 1;
+        }
+        sampled.extend(series[index].1.to_f64());
+        at = point.checked_add(interval);
+    }
+    sampled
+}
+
+/// Log returns of positive prices
+fn log_returns(prices: &[f64]) -> Vec<f64> {
+    prices
+        .windows(2)
+        .filter(|w| w[0] > 0.0 && w[1] > 0.0)
+        .map(|w| (w[1] / w[0]).ln())
+        .collect()
+}
+
+fn to_decimal(value: f64) -> Option<Decimal> {
+    Decimal::from_f64(value)
+}
+
+/// Calculate realized variance at a sampling interval
+///
+/// # Arguments
+/// * `series` - `(timestamp, price)` pairs sorted by timestamp
+/// * `interval` - Sampling interval in the series' timestamp unit (0 for
+///   every tick)
+///
+/// # Returns
+/// Sum of squared log returns, or None with fewer than two samples
+#[must_use]
+pub fn realized_variance(series: &[(i64, Decimal)], interval: i64) -> Option<Decimal> {
+    let returns = log_returns(&sample(series, interval));
+    if returns.is_empty() {
+        return None;
+    }
+    to_decimal(returns.iter().map(|r| r * r).sum())
+}
+
+/// Calculate bipower variation at a sampling interval
+///
+/// `(pi / 2) * sum(|r_i| |r_i-1|)`, a jump-robust estimate of the
+/// integrated variance.
+///
+/// # Returns
+/// Bipower variation, or None with fewer than two returns
+#[must_use]
+pub fn bipower_variation(series: &[(i64, Decimal)], interval: i64) -> Option<Decimal> {
+    let returns = log_returns(&sample(series, interval));
+    if returns.len() < 2 {
+        return None;
+    }
+    let sum: f64 = returns.windows(2).map(|w| w[0].abs() * w[1].abs()).sum();
+    to_decimal(PI / 2.0 * sum)
+}
+
+/// Parzen kernel weight
+fn parzen(x: f64) -> f64 {
+    if x <= 0.5 {
+        1.0 - 6.0 * x * x + 6.0 * x * x * x
+    } else if x <= 1.0 {
+        2.0 * (1.0 - x).powi(3)
+    } else {
+        0.0
+    }
+}
+
+/// Calculate a realized kernel with the Parzen kernel
+///
+/// `gamma_0 + 2 * sum(k(h / (H + 1)) * gamma_h)` for `h = 1..=H`, where
+/// `gamma_h` is the sum of `r_i * r_i-h`. Autocovariance terms correct for
+/// the bid-ask bounce that inflates tick-level realized variance.
+///
+/// # Arguments
+/// * `series` - `(timestamp, price)` pairs sorted by timestamp
+/// * `interval` - Sampling interval (0 for every tick)
+/// * `bandwidth` - Number of autocovariance lags H
+///
+/// # Returns
+/// Realized kernel, or None with fewer than two samples
+#[must_use]
+pub fn realized_kernel(
+    series: &[(i64, Decimal)],
+    interval: i64,
+    bandwidth: usize,
+) -> Option<Decimal> {
+    let returns = log_returns(&sample(series, interval));
+    if returns.is_empty() {
+        return None;
+    }
+    let gamma = |h: usize| -> f64 {
+        returns
+            .iter()
+            .skip(h)
+            .zip(&returns)
+            .map(|(a, b)| a * b)
+            .sum()
+    };
+
+    let mut kernel = gamma(0);
+    for h in 1..=bandwidth.min(returns.len().saturating_sub(1)) {
+        kernel += 2.0 * parzen(h as f64 / (bandwidth as f64 + 1.0)) * gamma(h);
+    }
+    to_decimal(kernel)
+}
+
+/// Calculate two-scale realized variance (Zhang, Mykland and Aït-Sahalia)
+///
+/// Averages the realized variance of `scales` offset subsamples of the tick
+/// series and removes the noise bias estimated from the all-tick realized
+/// variance.
+///
+/// # Arguments
+/// * `series` - Tick-by-tick `(timestamp, price)` pairs
+/// * `scales` - Number of subsamples K (the slow scale)
+///
+/// # Returns
+/// TSRV, or None when there are not more ticks than `scales`
+#[must_use]
+pub fn two_scale_realized_variance(series: &[(i64, Decimal)], scales: usize) -> Option<Decimal> {
+    let prices = sample(series, 0);
+    let scales = scales.max(1);
+    if prices.len() <= scales {
+        return None;
+    }
+
+    let all_returns = log_returns(&prices);
+    let n = all_returns.len() as f64;
+    let rv_all: f64 = all_returns.iter().map(|r| r * r).sum();
+
+    let rv_average = (0..scales)
+        .map(|offset| {
+            let subsample: Vec<f64> = prices
+                .iter()
+                .skip(offset)
+                .step_by(scales)
+                .copied()
+                .collect();
+            log_returns(&subsample).iter().map(|r| r * r).sum::<f64>()
+        })
+        .sum::<f64>()
+        / scales as f64;
+
+    let n_bar = (n - scales as f64 + 1.0) / scales as f64;
+    to_decimal(rv_average - n_bar / n * rv_all)
+}
+
+/// Realized variance at each sampling interval
+///
+/// Plotting the result against the interval shows where microstructure
+/// noise stops inflating the estimate; a sampling frequency is usually
+/// chosen where the curve flattens.
+///
+/// # Returns
+/// Vector of (interval, realized variance) for intervals with enough samples
+#[must_use]
+pub fn signature_plot(series: &[(i64, Decimal)], intervals: &[i64]) -> Vec<(i64, Decimal)> {
+    intervals
+        .iter()
+        .filter_map(|&interval| Some((interval, realized_variance(series, interval)?)))
+        .collect()
+}
+
+/// Average a per-bar estimate over bars with positive prices
+fn range_estimate<F>(bars: &[Bar], estimate: F) -> Option<Decimal>
+where
+    F: Fn(f64, f64, f64, f64) -> f64,
+{
+    let values: Vec<f64> = bars
+        .iter()
+        .filter_map(|bar| {
+            let (open, high, low, close) = (
+                bar.open.to_f64()?,
+                bar.high.to_f64()?,
+                bar.low.to_f64()?,
+                bar.close.to_f64()?,
+            );
+            (open > 0.0 && low > 0.0).then(|| estimate(open, high, low, close))
+        })
+        .collect();
+    if values.is_empty() {
+        return None;
+    }
+    to_decimal(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+/// Parkinson variance per bar, from the high-low range
+#[must_use]
+pub fn parkinson_variance(bars: &[Bar]) -> Option<Decimal> {
+    range_estimate(bars, |_, high, low, _| {
+        (high / low).ln().powi(2) / (4.0 * LN_2)
+    })
+}
+
+/// Garman-Klass variance per bar, from open, high, low and close
+#[must_use]
+pub fn garman_klass_variance(bars: &[Bar]) -> Option<Decimal> {
+    range_estimate(bars, |open, high, low, close| {
+        0.5 * (high / low).ln().powi(2) - (2.0 * LN_2 - 1.0) * (close / open).ln().powi(2)
+    })
+}
+
+/// Rogers-Satchell variance per bar, robust to a drift
+#[must_use]
+pub fn rogers_satchell_variance(bars: &[Bar]) -> Option<Decimal> {
+    range_estimate(bars, |open, high, low, close| {
+        (high / close).ln() * (high / open).ln() + (low / close).ln() * (low / open).ln()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bars::{build_bars, BarSpec};
+    use crate::types::Side;
+    use rust_decimal_macros::dec;
+
+    fn series(prices: &[f64]) -> Vec<(i64, Decimal)> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| (i as i64, Decimal::from_f64(p).unwrap()))
+            .collect()
+    }
+
+    fn close(a: Decimal, b: f64) -> bool {
+        (a.to_f64().unwrap() - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn test_realized_variance_sampling() {
+        let s = series(&[100.0, 101.0, 100.0, 101.0, 100.0]);
+        let tick = realized_variance(&s, 0).unwrap();
+        assert!(close(tick, 4.0 * (1.01f64).ln().powi(2)));
+
+        // Sampling every 2 seconds removes the bounce entirely
+        assert!(close(realized_variance(&s, 2).unwrap(), 0.0));
+        let plot = signature_plot(&s, &[1, 2, 10]);
+        assert_eq!(plot.len(), 2);
+        assert_eq!(plot[0], (1, tick));
+
+        // Previous-tick sampling across gaps
+        let gappy = vec![(0, dec!(100)), (5, dec!(110))];
+        assert_eq!(sample(&gappy, 2), vec![100.0, 100.0, 100.0]);
+
+        // Oversized grids are rejected, and stepping past i64::MAX stops
+        let day_ns = vec![(0, dec!(100)), (86_400_000_000_000, dec!(101))];
+        assert!(sample(&day_ns, 1).is_empty());
+        assert_eq!(realized_variance(&day_ns, 1), None);
+        let edge = vec![(i64::MAX - 1, dec!(100)), (i64::MAX, dec!(101))];
+        assert_eq!(sample(&edge, 2), vec![100.0]);
+        assert_eq!(sample(&edge, 1), vec![100.0, 101.0]);
+    }
+
+    #[test]
+    fn test_bipower_and_kernel() {
+        let s = series(&[100.0, 101.0, 102.0, 103.0, 104.0]);
+        let rv = realized_variance(&s, 0).unwrap();
+        let bv = bipower_variation(&s, 0).unwrap();
+        // Steady returns: BV is pi/2 * (n-1)/n of RV
+        assert!((bv / rv - dec!(1.1781)).abs() < dec!(0.01));
+
+        // Negative autocovariance from the bounce lowers the kernel below RV
+        let bounce = series(&[100.0, 101.0, 100.0, 101.0, 100.0, 101.0]);
+        let rk = realized_kernel(&bounce, 0, 2).unwrap();
+        assert!(rk < realized_variance(&bounce, 0).unwrap());
+        assert_eq!(
+            realized_kernel(&bounce, 0, 0),
+            realized_variance(&bounce, 0)
+        );
+    }
+
+    #[test]
+    fn test_two_scale() {
+        // Trend plus alternating noise: TSRV removes most of the noise
+        let prices: Vec<f64> = (0..200)
+            .map(|i| 100.0 + 0.01 * i as f64 + if i % 2 == 0 { 0.05 } else { -0.05 })
+            .collect();
+        let s = series(&prices);
+        let rv = realized_variance(&s, 0).unwrap();
+        let tsrv = two_scale_realized_variance(&s, 10).unwrap();
+        assert!(tsrv < rv / dec!(10));
+        assert!(two_scale_realized_variance(&s[..5], 10).is_none());
+        assert!(two_scale_realized_variance(&s[..10], 10).is_none());
+        assert!(two_scale_realized_variance(&s[..11], 10).is_some());
+    }
+
+    #[test]
+    fn test_range_estimators() {
+        let trades: Vec<Trade> = [100.0, 102.0, 99.0, 101.0]
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| Trade {
+                price: Decimal::from_f64(p).unwrap(),
+                quantity: dec!(1),
+                side: Side::Unknown,
+                timestamp: i as i64,
+            })
+            .collect();
+        let bars = build_bars(&trades, BarSpec::Tick(4));
+        let range = (102.0f64 / 99.0).ln();
+
+        assert!(close(
+            parkinson_variance(&bars).unwrap(),
+            range * range / (4.0 * LN_2)
+        ));
+        let gk = 0.5 * range * range - (2.0 * LN_2 - 1.0) * (1.01f64).ln().powi(2);
+        assert!(close(garman_klass_variance(&bars).unwrap(), gk));
+        let rs = (102.0f64 / 101.0).ln() * (102.0f64 / 100.0).ln()
+            + (99.0f64 / 101.0).ln() * (99.0f64 / 100.0).ln();
+        assert!(close(rogers_satchell_variance(&bars).unwrap(), rs));
+        assert!(parkinson_variance(&[]).is_none());
+    }
+}
@@ -0,0 +1,289 @@
+//! Bulk Volume Classification and VPIN
+//!
+//! Bulk Volume Classification (Easley, López de Prado and O'Hara) splits
+//! each bar's volume into buys and sells by the probability of its
+//! standardized price change. VPIN (Volume-Synchronized Probability of
+//! Informed Trading) averages the absolute buy/sell imbalance over a rolling
+//! window of equal-volume buckets.
+
+use crate::bars::{build_bars, Bar, BarSpec};
+use crate::stats::{normal_cdf, std_dev, student_t_cdf};
+use crate::types::Trade;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+
+/// Distribution used to turn standardized price changes into buy fractions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum BvcDistribution {
+    /// Standard normal
+    #[default]
+    Normal,
+    /// Student's t, with fatter tails
+    StudentT { degrees_of_freedom: f64 },
+}
+
+impl BvcDistribution {
+    fn cdf(self, z: f64) -> f64 {
+        match self {
+            BvcDistribution::Normal => normal_cdf(z),
+            BvcDistribution::StudentT { degrees_of_freedom } => {
+                student_t_cdf(z, degrees_of_freedom.max(f64::MIN_POSITIVE))
+            }
+        }
+    }
+}
+
+/// A bar's volume split into buys and sells
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ClassifiedVolume {
+    /// Bar end timestamp
+    pub timestamp: i64,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+}
+
+/// Split bar volumes with Bulk Volume Classification
+///
+/// The price change of each bar is its close minus the previous close (its
+/// open for the first bar), standardized by the standard deviation of all
+/// changes in the slice. The buy fraction is the distribution's CDF at that
+/// value; it is 0.5 for every bar when the deviation is zero or undefined.
+///
+/// # Arguments
+/// * `bars` - Consecutive bars
+/// * `distribution` - Normal or Student-t
+///
+/// # Returns
+/// One ClassifiedVolume per bar
+#[must_use]
+pub fn bulk_volume_classification(
+    bars: &[Bar],
+    distribution: BvcDistribution,
+) -> Vec<ClassifiedVolume> {
+    let changes: Vec<f64> = bars
+        .iter()
+        .enumerate()
+        .map(|(i, bar)| {
+            let previous = i.checked_sub(1).map_or(bar.open, |j| bars[j].close);
+            (bar.close - previous).to_f64().unwrap_or(0.0)
+        })
+        .collect();
+    let sigma = std_dev(&changes).filter(|s| *s > 0.0);
+
+    bars.iter()
+        .zip(changes)
+        .map(|(bar, change)| {
+            let fraction = sigma.map_or(0.5, |s| distribution.cdf(change / s));
+            let buy_volume = bar.volume
+                * Decimal::from_f64(fraction)
+                    .unwrap_or(dec!(0.5))
+                    .clamp(dec!(0), dec!(1));
+            ClassifiedVolume {
+                timestamp: bar.end,
+                buy_volume,
+                sell_volume: bar.volume - buy_volume,
+            }
+        })
+        .collect()
+}
+
+/// VPIN settings
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct VpinConfig {
+    /// Bars classified with BVC before bucketing
+    pub bar_spec: BarSpec,
+    /// Volume per bucket
+    pub bucket_volume: Decimal,
+    /// Number of buckets in the rolling window
+    pub window: usize,
+    pub distribution: BvcDistribution,
+}
+
+impl VpinConfig {
+    /// One-minute bars (with second timestamps), a 50-bucket window and the
+    /// normal distribution
+    #[must_use]
+    pub fn new(bucket_volume: Decimal) -> Self {
+        Self {
+            bar_spec: BarSpec::Time(60),
+            bucket_volume,
+            window: 50,
+            distribution: BvcDistribution::Normal,
+        }
+    }
+}
+
+/// Fill equal-volume buckets from classified bars
+///
+/// Bars spill into the next bucket pro rata, keeping their buy fraction.
+///
+/// # Returns
+/// (bucket end timestamp, |buy - sell|) per completed bucket
+fn bucket_imbalances(volumes: &[ClassifiedVolume], bucket_volume: Decimal) -> Vec<(i64, Decimal)> {
+    let mut buckets = Vec::new();
+    if bucket_volume <= dec!(0) {
+        return buckets;
+    }
+    let (mut buy, mut sell) = (dec!(0), dec!(0));
+
+    for bar in volumes {
+        let (mut bar_buy, mut bar_sell) = (bar.buy_volume, bar.sell_volume);
+        while bar_buy + bar_sell > dec!(0) {
+            let remaining = bar_buy + bar_sell;
+            let room = bucket_volume - (buy + sell);
+            let (take_buy, take_sell) = if remaining <= room {
+                (bar_buy, bar_sell)
+            } else {
+                let share = room / remaining;
+                (bar_buy * share, room - bar_buy * share)
+            };
+            buy += take_buy;
+            sell += take_sell;
+            bar_buy -= take_buy;
+            bar_sell -= take_sell;
+
+            if buy + sell >= bucket_volume {
+                buckets.push((bar.timestamp, (buy - sell).abs()));
+                buy = dec!(0);
+                sell = dec!(0);
+            }
+        }
+    }
+
+    buckets
+}
+
+/// Calculate VPIN over equal-volume buckets
+///
+/// # Arguments
+/// * `trades` - Executed trades, sorted by timestamp (sides are not used)
+/// * `config` - Bar sampling, bucket size, window and distribution
+///
+/// # Returns
+/// Vector of (bucket end timestamp, VPIN), starting at the first bucket
+/// that completes a full window
+#[must_use]
+pub fn calculate_vpin(trades: &[Trade], config: &VpinConfig) -> Vec<(i64, Decimal)> {
+    let bars = build_bars(trades, config.bar_spec);
+    let volumes = bulk_volume_classification(&bars, config.distribution);
+    let buckets = bucket_imbalances(&volumes, config.bucket_volume);
+
+    let window = config.window.max(1);
+    let denominator = config.bucket_volume * Decimal::from(window);
+    let mut recent: VecDeque<Decimal> = VecDeque::with_capacity(window);
+    let mut total = dec!(0);
+    let mut series = Vec::new();
+
+    for (timestamp, imbalance) in buckets {
+        recent.push_back(imbalance);
+        total += imbalance;
+        if recent.len() > window {
+            total -= recent.pop_front().unwrap_or_default();
+        }
+        if recent.len() == window {
+            series.push((timestamp, total / denominator));
+        }
+    }
+
+    series
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+
+    fn trade(price: Decimal, quantity: Decimal, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side: Side::Unknown,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_bvc_split() {
+        let bars = build_bars(
+            &[
+                trade(dec!(100), dec!(10), 0),
+                trade(dec!(101), dec!(10), 60),
+                trade(dec!(101), dec!(10), 120),
+                trade(dec!(100), dec!(10), 180),
+            ],
+            BarSpec::Time(60),
+        );
+        let split = bulk_volume_classification(&bars, BvcDistribution::Normal);
+
+        assert_eq!(split.len(), 4);
+        assert_eq!(split[0].buy_volume.round_dp(4), dec!(5));
+        assert!(split[1].buy_volume > dec!(5));
+        assert_eq!(split[2].buy_volume.round_dp(4), dec!(5));
+        assert!(split[3].buy_volume < dec!(5));
+        // Up and down moves of the same size split symmetrically
+        assert_eq!(
+            split[1].buy_volume.round_dp(6),
+            split[3].sell_volume.round_dp(6)
+        );
+        assert!(split
+            .iter()
+            .all(|v| v.buy_volume + v.sell_volume == dec!(10)));
+
+        // Fatter tails push less volume to one side for the same move
+        let t = bulk_volume_classification(
+            &bars,
+            BvcDistribution::StudentT {
+                degrees_of_freedom: 1.0,
+            },
+        );
+        assert!(t[1].buy_volume < split[1].buy_volume);
+    }
+
+    #[test]
+    fn test_bucket_spill() {
+        let volumes = vec![
+            ClassifiedVolume {
+                timestamp: 1,
+                buy_volume: dec!(6),
+                sell_volume: dec!(2),
+            },
+            ClassifiedVolume {
+                timestamp: 2,
+                buy_volume: dec!(0),
+                sell_volume: dec!(12),
+            },
+        ];
+
+        // Bucket 1: 6/2 plus 0/2 spilled from the second bar, bucket 2: 0/10
+        assert_eq!(
+            bucket_imbalances(&volumes, dec!(10)),
+            vec![(2, dec!(2)), (2, dec!(10))]
+        );
+    }
+
+    #[test]
+    fn test_vpin_series() {
+        let trades: Vec<Trade> = [100, 102, 104, 103, 105, 104]
+            .into_iter()
+            .enumerate()
+            .map(|(i, p)| trade(Decimal::from(p), dec!(10), i as i64 * 60))
+            .collect();
+        let config = VpinConfig {
+            window: 3,
+            ..VpinConfig::new(dec!(10))
+        };
+
+        let series = calculate_vpin(&trades, &config);
+
+        // Six one-bar buckets, so four full windows
+        assert_eq!(series.len(), 4);
+        assert_eq!(series[0].0, 180);
+        assert!(series
+            .iter()
+            .all(|&(_, vpin)| vpin >= dec!(0) && vpin <= dec!(1)));
+        assert!(calculate_vpin(&trades[..2], &config).is_empty());
+    }
+}
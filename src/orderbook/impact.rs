@@ -0,0 +1,270 @@
+//! Market Impact Simulation
+//!
+//! Walks a snapshot to answer what a market order of a given size would
+//! cost right now, and the inverse: how much can be filled before the
+//! average price slips past a limit. A buy consumes the asks and a sell the
+//! bids. Slippage is in basis points and positive when it is a cost.
+
+use super::mid_price;
+use crate::metrics::weighted_mid_price;
+use crate::types::{Level, OrderBook, Side};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Reference price that slippage is measured against
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SlippageReference {
+    /// Mid of the best bid and ask
+    #[default]
+    Mid,
+    /// [`crate::metrics::weighted_mid_price`]
+    WeightedMid,
+}
+
+impl SlippageReference {
+    /// Reference price of a book, None when the book is one-sided
+    #[must_use]
+    pub fn price(self, orderbook: &OrderBook) -> Option<Decimal> {
+        match self {
+            SlippageReference::Mid => mid_price(orderbook),
+            SlippageReference::WeightedMid => weighted_mid_price(orderbook),
+        }
+    }
+}
+
+/// Result of walking the book with a market order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarketImpact {
+    pub side: Side,
+    pub requested: Decimal,
+    pub filled: Decimal,
+    pub notional: Decimal,
+    /// Volume-weighted fill price, None when nothing filled
+    pub average_price: Option<Decimal>,
+    /// Price of the last level touched
+    pub worst_price: Option<Decimal>,
+    /// Levels touched, including a partially filled last level
+    pub levels_consumed: usize,
+    /// Whether the visible book covered the whole order
+    pub fully_filled: bool,
+    /// Slippage of the average price versus the mid, in bps
+    pub slippage_mid_bps: Option<Decimal>,
+    /// Slippage of the average price versus the weighted mid, in bps
+    pub slippage_weighted_mid_bps: Option<Decimal>,
+}
+
+/// Levels a market order of `side` consumes, best first
+fn opposite_levels(orderbook: &OrderBook, side: Side) -> Option<&[Level]> {
+    match side {
+        Side::Buy => Some(&orderbook.asks),
+        Side::Sell => Some(&orderbook.bids),
+        Side::Unknown => None,
+    }
+}
+
+/// +1 for buys and -1 for sells, so that positive slippage is a cost
+fn direction(side: Side) -> Decimal {
+    if side == Side::Sell {
+        dec!(-1)
+    } else {
+        dec!(1)
+    }
+}
+
+/// Slippage of `price` versus `reference` in bps, signed as a cost
+fn slippage_bps(side: Side, price: Decimal, reference: Decimal) -> Option<Decimal> {
+    if reference.is_zero() {
+        return None;
+    }
+    Some(direction(side) * (price - reference) / reference * dec!(10000))
+}
+
+/// Simulate a market order against a snapshot
+///
+/// # Arguments
+/// * `orderbook` - The order book to walk
+/// * `side` - Aggressor side (`Buy` lifts the asks, `Sell` hits the bids)
+/// * `quantity` - Order size
+///
+/// # Returns
+/// MarketImpact, or None for `Side::Unknown`. Orders larger than the
+/// visible book are filled as far as it goes.
+#[must_use]
+pub fn simulate_market_order(
+    orderbook: &OrderBook,
+    side: Side,
+    quantity: Decimal,
+) -> Option<MarketImpact> {
+    let levels = opposite_levels(orderbook, side)?;
+    let mut filled = dec!(0);
+    let mut notional = dec!(0);
+    let mut worst_price = None;
+    let mut levels_consumed = 0;
+
+    for level in levels {
+        if filled >= quantity {
+            break;
+        }
+        let take = level.quantity.min(quantity - filled);
+        if take <= dec!(0) {
+            continue;
+        }
+        filled += take;
+        notional += take * level.price;
+        worst_price = Some(level.price);
+        levels_consumed += 1;
+    }
+
+    let average_price = (filled > dec!(0)).then(|| notional / filled);
+    let slippage = |reference: SlippageReference| {
+        slippage_bps(side, average_price?, reference.price(orderbook)?)
+    };
+
+    Some(MarketImpact {
+        side,
+        requested: quantity,
+        filled,
+        notional,
+        average_price,
+        worst_price,
+        levels_consumed,
+        fully_filled: filled >= quantity,
+        slippage_mid_bps: slippage(SlippageReference::Mid),
+        slippage_weighted_mid_bps: slippage(SlippageReference::WeightedMid),
+    })
+}
+
+/// Find the largest market order whose average price stays within a limit
+///
+/// # Arguments
+/// * `orderbook` - The order book to walk
+/// * `side` - Aggressor side
+/// * `max_bps` - Maximum slippage of the average price, in bps
+/// * `reference` - Price the slippage is measured against
+///
+/// # Returns
+/// The maximum size (zero when even the best level is too far), or None for
+/// `Side::Unknown` or a one-sided book. The whole side is returned when
+/// the limit is never reached.
+#[must_use]
+pub fn max_size_within_bps(
+    orderbook: &OrderBook,
+    side: Side,
+    max_bps: Decimal,
+    reference: SlippageReference,
+) -> Option<Decimal> {
+    let levels = opposite_levels(orderbook, side)?;
+    let reference = reference.price(orderbook)?;
+    let d = direction(side);
+    let limit = reference * (dec!(1) + d * max_bps / dec!(10000));
+
+    let mut filled = dec!(0);
+    let mut notional = dec!(0);
+    for level in levels {
+        if d * (level.price - limit) <= dec!(0) {
+            filled += level.quantity;
+            notional += level.quantity * level.price;
+            continue;
+        }
+        // Take x from this level while d * (average - limit) <= 0:
+        // x * d * (price - limit) <= d * (limit * filled - notional)
+        let room = d * (limit * filled - notional);
+        if room <= dec!(0) {
+            break;
+        }
+        let take = room / (d * (level.price - limit));
+        if take < level.quantity {
+            filled += take;
+            break;
+        }
+        filled += level.quantity;
+        notional += level.quantity * level.price;
+    }
+    Some(filled)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn level(price: Decimal, quantity: Decimal) -> Level {
+        Level { price, quantity }
+    }
+
+    fn book() -> OrderBook {
+        OrderBook {
+            bids: vec![
+                level(dec!(99), dec!(1)),
+                level(dec!(98), dec!(2)),
+                level(dec!(97), dec!(5)),
+            ],
+            asks: vec![
+                level(dec!(101), dec!(3)),
+                level(dec!(102), dec!(2)),
+                level(dec!(104), dec!(5)),
+            ],
+            timestamp: 0,
+        }
+    }
+
+    #[test]
+    fn test_simulate_buy() {
+        let impact = simulate_market_order(&book(), Side::Buy, dec!(4)).unwrap();
+        assert_eq!(impact.filled, dec!(4));
+        assert_eq!(impact.notional, dec!(405));
+        assert_eq!(impact.average_price, Some(dec!(101.25)));
+        assert_eq!(impact.worst_price, Some(dec!(102)));
+        assert_eq!(impact.levels_consumed, 2);
+        assert!(impact.fully_filled);
+        // Mid 100: 1.25 / 100 = 125 bps
+        assert_eq!(impact.slippage_mid_bps, Some(dec!(125)));
+        // Weighted mid (99 * 3 + 101 * 1) / 4 = 99.5
+        let expected = (dec!(101.25) - dec!(99.5)) / dec!(99.5) * dec!(10000);
+        assert_eq!(impact.slippage_weighted_mid_bps, Some(expected));
+    }
+
+    #[test]
+    fn test_simulate_sell_and_exhaustion() {
+        let impact = simulate_market_order(&book(), Side::Sell, dec!(20)).unwrap();
+        assert_eq!(impact.filled, dec!(8));
+        assert!(!impact.fully_filled);
+        assert_eq!(impact.levels_consumed, 3);
+        assert_eq!(impact.worst_price, Some(dec!(97)));
+        assert!(impact.slippage_mid_bps.unwrap() > dec!(0));
+
+        assert!(simulate_market_order(&book(), Side::Unknown, dec!(1)).is_none());
+    }
+
+    #[test]
+    fn test_max_size_within_bps() {
+        let ob = book();
+        // Limit 101.2: 3 @ 101 plus x @ 102 where (303 + 102x) / (3 + x) = 101.2
+        let size = max_size_within_bps(&ob, Side::Buy, dec!(120), SlippageReference::Mid);
+        assert_eq!(size, Some(dec!(3.75)));
+        let impact = simulate_market_order(&ob, Side::Buy, size.unwrap()).unwrap();
+        assert_eq!(impact.slippage_mid_bps, Some(dec!(120)));
+
+        // Limit 101.5 lies past the second level: 3 @ 101, 2 @ 102, 0.2 @ 104
+        assert_eq!(
+            max_size_within_bps(&ob, Side::Buy, dec!(150), SlippageReference::Mid),
+            Some(dec!(5.2))
+        );
+
+        // Inside the half spread nothing can be filled
+        assert_eq!(
+            max_size_within_bps(&ob, Side::Buy, dec!(50), SlippageReference::Mid),
+            Some(dec!(0))
+        );
+
+        // Limit 98.5: 1 @ 99 plus x @ 98 where (99 + 98x) / (1 + x) = 98.5 gives x = 1
+        assert_eq!(
+            max_size_within_bps(&ob, Side::Sell, dec!(150), SlippageReference::Mid),
+            Some(dec!(2))
+        );
+        assert_eq!(
+            max_size_within_bps(&ob, Side::Sell, dec!(10000), SlippageReference::Mid),
+            Some(dec!(8))
+        );
+    }
+}
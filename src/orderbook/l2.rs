@@ -0,0 +1,574 @@
+//! Incremental Level 2 Order Book
+//!
+//! Maintains aggregated price levels in ordered maps so that bids are always
+//! best-first (descending) and asks best-first (ascending), regardless of the
+//! order in which snapshots and updates arrive.
+//!
+//! Every update carries a sequence number. Stale updates are rejected, and a
+//! gap in the sequence marks the book as desynced until a fresh snapshot is
+//! applied; analytics refuse to run on a desynced book.
+
+use crate::types::{Level, OrderBook};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Side of the book a level belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BookSide {
+    Bid,
+    Ask,
+}
+
+impl fmt::Display for BookSide {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            BookSide::Bid => "bid",
+            BookSide::Ask => "ask",
+        })
+    }
+}
+
+/// A single price level change
+///
+/// `quantity` is the new total resting at `price`; zero removes the level.
+/// `update_id` must be exactly one more than the previous update (or the
+/// snapshot id) for the update to be applied.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelUpdate {
+    pub update_id: u64,
+    pub side: BookSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    pub timestamp: i64,
+}
+
+/// Errors raised while maintaining an [`L2Book`] or [`super::L3Book`]
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BookError {
+    /// Update id at or below the last applied id; the update was ignored
+    Stale { update_id: u64, last_update_id: u64 },
+    /// One or more updates were missed; the book is now desynced
+    Gap { expected: u64, received: u64 },
+    /// The book needs a fresh snapshot before it can be used
+    Desynced,
+    /// Order id is not resting in the book
+    UnknownOrder { order_id: u64 },
+    /// Order id is already resting in the book
+    DuplicateOrder { order_id: u64 },
+    /// Quantity is not valid for the order
+    InvalidQuantity { order_id: u64, quantity: Decimal },
+}
+
+impl fmt::Display for BookError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BookError::Stale {
+                update_id,
+                last_update_id,
+            } => write!(
+                f,
+                "stale update {update_id} (last applied {last_update_id})"
+            ),
+            BookError::Gap { expected, received } => {
+                write!(f, "sequence gap: expected {expected}, received {received}")
+            }
+            BookError::Desynced => write!(f, "order book is desynced, snapshot required"),
+            BookError::UnknownOrder { order_id } => write!(f, "unknown order {order_id}"),
+            BookError::DuplicateOrder { order_id } => write!(f, "duplicate order {order_id}"),
+            BookError::InvalidQuantity { order_id, quantity } => {
+                write!(f, "invalid quantity {quantity} for order {order_id}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for BookError {}
+
+/// Mutable L2 order book built from a snapshot plus incremental updates
+#[derive(Debug, Clone, Default)]
+pub struct L2Book {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    timestamp: i64,
+    last_update_id: u64,
+    synced: bool,
+}
+
+impl L2Book {
+    /// Create an empty book (desynced until the first snapshot)
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a book initialized from a snapshot taken at `update_id`
+    #[must_use]
+    pub fn from_snapshot(snapshot: &OrderBook, update_id: u64) -> Self {
+        let mut book = Self::new();
+        book.apply_snapshot(snapshot, update_id);
+        book
+    }
+
+    /// Replace the whole book with a full snapshot taken at `update_id`
+    ///
+    /// Levels with zero (or negative) quantity are dropped; input ordering
+    /// does not matter. This is the only way to resync a desynced book.
+    pub fn apply_snapshot(&mut self, snapshot: &OrderBook, update_id: u64) {
+        self.bids.clear();
+        self.asks.clear();
+        for level in &snapshot.bids {
+            Self::set(&mut self.bids, level.price, level.quantity);
+        }
+        for level in &snapshot.asks {
+            Self::set(&mut self.asks, level.price, level.quantity);
+        }
+        self.timestamp = snapshot.timestamp;
+        self.last_update_id = update_id;
+        self.synced = true;
+    }
+
+    /// Apply a single level update
+    ///
+    /// # Errors
+    /// * [`BookError::Desynced`] if the book is waiting for a snapshot
+    /// * [`BookError::Stale`] if the update was already applied (book
+    ///   unchanged); after id `u64::MAX` every update is stale until a new
+    ///   snapshot is applied
+    /// * [`BookError::Gap`] if updates were missed (book becomes desynced)
+    pub fn apply_update(&mut self, update: &LevelUpdate) -> Result<(), BookError> {
+        self.ensure_synced()?;
+
+        if update.update_id <= self.last_update_id {
+            return Err(BookError::Stale {
+                update_id: update.update_id,
+                last_update_id: self.last_update_id,
+            });
+        }
+
+        // Cannot overflow: the stale check above rejects everything at u64::MAX
+        let expected = self.last_update_id + 1;
+
+        if update.update_id != expected {
+            self.synced = false;
+            return Err(BookError::Gap {
+                expected,
+                received: update.update_id,
+            });
+        }
+
+        let levels = match update.side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        };
+        Self::set(levels, update.price, update.quantity);
+        self.timestamp = update.timestamp;
+        self.last_update_id = update.update_id;
+        Ok(())
+    }
+
+    /// Mark the book as desynced, e.g. after a feed disconnect
+    pub fn invalidate(&mut self) {
+        self.synced = false;
+    }
+
+    fn set(levels: &mut BTreeMap<Decimal, Decimal>, price: Decimal, quantity: Decimal) {
+        if quantity <= dec!(0) {
+            levels.remove(&price);
+        } else {
+            levels.insert(price, quantity);
+        }
+    }
+
+    /// Timestamp of the last snapshot or update applied
+    #[must_use]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Id of the last snapshot or update applied
+    #[must_use]
+    pub fn last_update_id(&self) -> u64 {
+        self.last_update_id
+    }
+
+    /// Whether the book is in sync with the feed
+    #[must_use]
+    pub fn is_synced(&self) -> bool {
+        self.synced
+    }
+
+    fn ensure_synced(&self) -> Result<(), BookError> {
+        if self.synced {
+            Ok(())
+        } else {
+            Err(BookError::Desynced)
+        }
+    }
+
+    /// Quantity resting at a price level, if any
+    #[must_use]
+    pub fn quantity_at(&self, side: BookSide, price: Decimal) -> Option<Decimal> {
+        match side {
+            BookSide::Bid => self.bids.get(&price).copied(),
+            BookSide::Ask => self.asks.get(&price).copied(),
+        }
+    }
+
+    /// Bid levels, best (highest) first
+    pub fn bids(&self) -> impl Iterator<Item = Level> + '_ {
+        self.bids
+            .iter()
+            .rev()
+            .map(|(&price, &quantity)| Level { price, quantity })
+    }
+
+    /// Ask levels, best (lowest) first
+    pub fn asks(&self) -> impl Iterator<Item = Level> + '_ {
+        self.asks
+            .iter()
+            .map(|(&price, &quantity)| Level { price, quantity })
+    }
+
+    /// Number of (bid, ask) price levels
+    #[must_use]
+    pub fn depth(&self) -> (usize, usize) {
+        (self.bids.len(), self.asks.len())
+    }
+
+    /// Whether both sides are empty
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.bids.is_empty() && self.asks.is_empty()
+    }
+
+    /// Best bid price
+    #[must_use]
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.keys().next_back().copied()
+    }
+
+    /// Best ask price
+    #[must_use]
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Mid price, same as [`super::mid_price`]
+    ///
+    /// # Errors
+    /// [`BookError::Desynced`] if the book needs a snapshot
+    pub fn mid_price(&self) -> Result<Option<Decimal>, BookError> {
+        self.ensure_synced()?;
+        match (self.best_bid(), self.best_ask()) {
+            (Some(bid), Some(ask)) => Ok(Some((bid + ask) / dec!(2))),
+            _ => Ok(None),
+        }
+    }
+
+    /// Bid-ask spread, same as [`super::calculate_spread`]
+    ///
+    /// # Errors
+    /// [`BookError::Desynced`] if the book needs a snapshot
+    pub fn spread(&self) -> Result<Option<(Decimal, Decimal)>, BookError> {
+        self.ensure_synced()?;
+        let (Some(best_bid), Some(best_ask)) = (self.best_bid(), self.best_ask()) else {
+            return Ok(None);
+        };
+        let spread = best_ask - best_bid;
+        let spread_pct = (spread / best_bid) * dec!(100);
+
+        Ok(Some((spread, spread_pct)))
+    }
+
+    /// Depth imbalance, same as [`super::calculate_imbalance`]
+    ///
+    /// # Errors
+    /// [`BookError::Desynced`] if the book needs a snapshot
+    pub fn imbalance(&self, depth: Option<usize>) -> Result<Decimal, BookError> {
+        self.ensure_synced()?;
+        let depth = depth.unwrap_or(usize::MAX);
+
+        let total_bid_volume: Decimal = self.bids.values().rev().take(depth).sum();
+        let total_ask_volume: Decimal = self.asks.values().take(depth).sum();
+
+        let total = total_bid_volume + total_ask_volume;
+        if total == dec!(0) {
+            return Ok(dec!(0));
+        }
+
+        Ok((total_bid_volume - total_ask_volume) / total)
+    }
+
+    /// Materialize an [`OrderBook`] view with the top `depth` levels per side
+    /// (None for all levels)
+    ///
+    /// # Errors
+    /// [`BookError::Desynced`] if the book needs a snapshot
+    pub fn to_orderbook(&self, depth: Option<usize>) -> Result<OrderBook, BookError> {
+        self.ensure_synced()?;
+        let depth = depth.unwrap_or(usize::MAX);
+        Ok(OrderBook {
+            bids: self.bids().take(depth).collect(),
+            asks: self.asks().take(depth).collect(),
+            timestamp: self.timestamp,
+        })
+    }
+}
+
+/// Level updates that turn each snapshot into the next
+///
+/// Useful when only snapshots were captured. Update ids start at 1, so the
+/// updates apply to `L2Book::from_snapshot(&books[0], 0)`. Several changes
+/// to a level between two snapshots collapse into one update, so message
+/// counts are a lower bound of the real feed.
+///
+/// # Arguments
+/// * `books` - Consecutive snapshots sorted by timestamp
+///
+/// # Returns
+/// One update per changed level, stamped with the later snapshot's time
+#[must_use]
+pub fn level_updates(books: &[OrderBook]) -> Vec<LevelUpdate> {
+    let mut updates = Vec::new();
+    for pair in books.windows(2) {
+        let (prev, cur) = (&pair[0], &pair[1]);
+        for (side, before, after) in [
+            (BookSide::Bid, &prev.bids, &cur.bids),
+            (BookSide::Ask, &prev.asks, &cur.asks),
+        ] {
+            let mut levels: BTreeMap<Decimal, (Decimal, Decimal)> = BTreeMap::new();
+            for level in before {
+                levels.entry(level.price).or_default().0 = level.quantity;
+            }
+            for level in after {
+                levels.entry(level.price).or_default().1 = level.quantity;
+            }
+            for (price, (old, new)) in levels {
+                if old != new {
+                    updates.push(LevelUpdate {
+                        update_id: updates.len() as u64 + 1,
+                        side,
+                        price,
+                        quantity: new,
+                        timestamp: cur.timestamp,
+                    });
+                }
+            }
+        }
+    }
+    updates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::{calculate_imbalance, calculate_spread, mid_price};
+
+    fn update(update_id: u64, side: BookSide, price: Decimal, quantity: Decimal) -> LevelUpdate {
+        LevelUpdate {
+            update_id,
+            side,
+            price,
+            quantity,
+            timestamp: 1000 + update_id as i64,
+        }
+    }
+
+    fn unsorted_snapshot() -> OrderBook {
+        OrderBook {
+            bids: vec![
+                Level {
+                    price: dec!(99.0),
+                    quantity: dec!(2.0),
+                },
+                Level {
+                    price: dec!(100.0),
+                    quantity: dec!(1.0),
+                },
+                Level {
+                    price: dec!(98.0),
+                    quantity: dec!(0),
+                },
+            ],
+            asks: vec![
+                Level {
+                    price: dec!(102.0),
+                    quantity: dec!(3.0),
+                },
+                Level {
+                    price: dec!(101.0),
+                    quantity: dec!(1.5),
+                },
+            ],
+            timestamp: 1000,
+        }
+    }
+
+    #[test]
+    fn test_snapshot_sorts_levels() {
+        let book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+        let view = book.to_orderbook(None).unwrap();
+
+        assert_eq!(book.depth(), (2, 2));
+        assert_eq!(view.bids[0].price, dec!(100.0));
+        assert_eq!(view.bids[1].price, dec!(99.0));
+        assert_eq!(view.asks[0].price, dec!(101.0));
+        assert_eq!(view.asks[1].price, dec!(102.0));
+        assert_eq!(view.timestamp, 1000);
+    }
+
+    #[test]
+    fn test_apply_updates() {
+        let mut book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+
+        book.apply_update(&update(11, BookSide::Bid, dec!(100.5), dec!(4.0)))
+            .unwrap();
+        book.apply_update(&update(12, BookSide::Ask, dec!(101.0), dec!(0)))
+            .unwrap();
+
+        assert_eq!(book.best_bid(), Some(dec!(100.5)));
+        assert_eq!(book.best_ask(), Some(dec!(102.0)));
+        assert_eq!(book.quantity_at(BookSide::Ask, dec!(101.0)), None);
+        assert_eq!(book.timestamp(), 1012);
+        assert_eq!(book.last_update_id(), 12);
+    }
+
+    #[test]
+    fn test_stale_update_rejected() {
+        let mut book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+
+        let result = book.apply_update(&update(10, BookSide::Bid, dec!(100.0), dec!(9.0)));
+
+        assert_eq!(
+            result,
+            Err(BookError::Stale {
+                update_id: 10,
+                last_update_id: 10
+            })
+        );
+        assert!(book.is_synced());
+        assert_eq!(
+            book.quantity_at(BookSide::Bid, dec!(100.0)),
+            Some(dec!(1.0))
+        );
+    }
+
+    #[test]
+    fn test_level_updates_replay_snapshots() {
+        let first = unsorted_snapshot();
+        let mut second = unsorted_snapshot();
+        second.bids.remove(1);
+        second.asks[0].quantity = dec!(4.0);
+        second.asks.push(Level {
+            price: dec!(103.0),
+            quantity: dec!(1.0),
+        });
+        second.timestamp = 1001;
+
+        let updates = level_updates(&[first.clone(), second]);
+        assert_eq!(updates.len(), 3);
+        assert_eq!(updates[0].update_id, 1);
+        assert_eq!(updates[0].quantity, dec!(0));
+        assert!(updates.iter().all(|u| u.timestamp == 1001));
+
+        let mut book = L2Book::from_snapshot(&first, 0);
+        for update in &updates {
+            book.apply_update(update).unwrap();
+        }
+        assert_eq!(book.best_bid(), Some(dec!(99.0)));
+        assert_eq!(
+            book.quantity_at(BookSide::Ask, dec!(102.0)),
+            Some(dec!(4.0))
+        );
+        assert_eq!(book.depth(), (1, 3));
+    }
+
+    #[test]
+    fn test_max_update_id_is_stale() {
+        let mut book = L2Book::from_snapshot(&unsorted_snapshot(), u64::MAX);
+
+        let result = book.apply_update(&update(u64::MAX, BookSide::Bid, dec!(100.0), dec!(9.0)));
+        assert_eq!(
+            result,
+            Err(BookError::Stale {
+                update_id: u64::MAX,
+                last_update_id: u64::MAX
+            })
+        );
+        assert!(book.is_synced());
+        assert_eq!(
+            book.quantity_at(BookSide::Bid, dec!(100.0)),
+            Some(dec!(1.0))
+        );
+
+        book.apply_snapshot(&unsorted_snapshot(), 1);
+        assert!(book
+            .apply_update(&update(2, BookSide::Bid, dec!(100.0), dec!(9.0)))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_gap_desyncs_until_snapshot() {
+        let mut book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+
+        let result = book.apply_update(&update(12, BookSide::Bid, dec!(100.0), dec!(9.0)));
+        assert_eq!(
+            result,
+            Err(BookError::Gap {
+                expected: 11,
+                received: 12
+            })
+        );
+        assert!(!book.is_synced());
+        assert_eq!(book.imbalance(None), Err(BookError::Desynced));
+        assert_eq!(
+            book.apply_update(&update(11, BookSide::Bid, dec!(100.0), dec!(9.0))),
+            Err(BookError::Desynced)
+        );
+
+        book.apply_snapshot(&unsorted_snapshot(), 20);
+        assert!(book.is_synced());
+        assert!(book.imbalance(None).is_ok());
+        assert!(book
+            .apply_update(&update(21, BookSide::Ask, dec!(101.0), dec!(2.0)))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_new_book_requires_snapshot() {
+        let mut book = L2Book::new();
+
+        assert_eq!(
+            book.apply_update(&update(1, BookSide::Bid, dec!(100.0), dec!(1.0))),
+            Err(BookError::Desynced)
+        );
+        assert_eq!(book.mid_price(), Err(BookError::Desynced));
+    }
+
+    #[test]
+    fn test_analytics_match_free_functions() {
+        let book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+        let view = book.to_orderbook(None).unwrap();
+
+        assert_eq!(book.spread(), Ok(calculate_spread(&view)));
+        assert_eq!(book.mid_price(), Ok(mid_price(&view)));
+        assert_eq!(book.imbalance(None), Ok(calculate_imbalance(&view, None)));
+        assert_eq!(
+            book.imbalance(Some(1)),
+            Ok(calculate_imbalance(&view, Some(1)))
+        );
+    }
+
+    #[test]
+    fn test_to_orderbook_depth() {
+        let book = L2Book::from_snapshot(&unsorted_snapshot(), 10);
+        let view = book.to_orderbook(Some(1)).unwrap();
+
+        assert_eq!(view.bids.len(), 1);
+        assert_eq!(view.asks.len(), 1);
+    }
+}
@@ -0,0 +1,567 @@
+//! Level 3 (Order-by-Order) Order Book
+//!
+//! Tracks every resting order by id with a FIFO queue per price level, and
+//! aggregates down to the [`OrderBook`]/[`Level`] shape so the L2 analytics
+//! in this crate keep working on top of it.
+
+use super::{BookError, BookSide};
+use crate::types::{Level, OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A resting order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Order {
+    pub order_id: u64,
+    pub side: BookSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// Time the order obtained its current queue position
+    pub timestamp: i64,
+}
+
+/// Order-level book event
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OrderEvent {
+    /// New order joins the back of the queue at `price`
+    Add {
+        order_id: u64,
+        side: BookSide,
+        price: Decimal,
+        quantity: Decimal,
+        timestamp: i64,
+    },
+    /// Change price and/or quantity of a resting order
+    Modify {
+        order_id: u64,
+        price: Decimal,
+        quantity: Decimal,
+        timestamp: i64,
+    },
+    /// Cancel `quantity` of an order, or all of it when `None`
+    Cancel {
+        order_id: u64,
+        quantity: Option<Decimal>,
+        timestamp: i64,
+    },
+    /// Execute `quantity` of a resting order, at `price` if given, otherwise
+    /// at the order's limit price
+    Execute {
+        order_id: u64,
+        quantity: Decimal,
+        price: Option<Decimal>,
+        timestamp: i64,
+    },
+}
+
+/// Result of executing against a resting order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Execution {
+    pub order_id: u64,
+    /// Side of the resting (passive) order
+    pub side: BookSide,
+    pub price: Decimal,
+    pub quantity: Decimal,
+    /// Quantity left on the resting order after the execution
+    pub remaining: Decimal,
+    pub timestamp: i64,
+}
+
+impl Execution {
+    /// Convert to a tape [`Trade`]; the aggressor is opposite the resting order
+    #[must_use]
+    pub fn to_trade(&self) -> Trade {
+        Trade {
+            price: self.price,
+            quantity: self.quantity,
+            side: match self.side {
+                BookSide::Bid => Side::Sell,
+                BookSide::Ask => Side::Buy,
+            },
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+/// FIFO queue of order ids at one price, keyed by arrival sequence so an
+/// order leaves the middle of a busy level without a scan
+#[derive(Debug, Clone, Default)]
+struct PriceQueue {
+    orders: BTreeMap<u64, u64>,
+    total: Decimal,
+}
+
+/// Order-by-order book keyed by order id
+#[derive(Debug, Clone, Default)]
+pub struct L3Book {
+    orders: HashMap<u64, Order>,
+    /// Arrival sequence of each resting order in its price queue
+    sequences: HashMap<u64, u64>,
+    next_sequence: u64,
+    bids: BTreeMap<Decimal, PriceQueue>,
+    asks: BTreeMap<Decimal, PriceQueue>,
+    timestamp: i64,
+}
+
+impl L3Book {
+    /// Create an empty book
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply an order event
+    ///
+    /// # Returns
+    /// The execution for `Execute` events, `None` otherwise
+    ///
+    /// # Errors
+    /// See [`L3Book::add_order`], [`L3Book::modify_order`],
+    /// [`L3Book::cancel_order`] and [`L3Book::execute_order`]
+    pub fn apply(&mut self, event: &OrderEvent) -> Result<Option<Execution>, BookError> {
+        match *event {
+            OrderEvent::Add {
+                order_id,
+                side,
+                price,
+                quantity,
+                timestamp,
+            } => self
+                .add_order(order_id, side, price, quantity, timestamp)
+                .map(|()| None),
+            OrderEvent::Modify {
+                order_id,
+                price,
+                quantity,
+                timestamp,
+            } => self
+                .modify_order(order_id, price, quantity, timestamp)
+                .map(|()| None),
+            OrderEvent::Cancel {
+                order_id,
+                quantity,
+                timestamp,
+            } => self
+                .cancel_order(order_id, quantity, timestamp)
+                .map(|_| None),
+            OrderEvent::Execute {
+                order_id,
+                quantity,
+                price,
+                timestamp,
+            } => self
+                .execute_order(order_id, quantity, price, timestamp)
+                .map(Some),
+        }
+    }
+
+    /// Add a new order at the back of its price queue
+    ///
+    /// # Errors
+    /// * [`BookError::DuplicateOrder`] if the id is already resting
+    /// * [`BookError::InvalidQuantity`] if `quantity` is not positive
+    pub fn add_order(
+        &mut self,
+        order_id: u64,
+        side: BookSide,
+        price: Decimal,
+        quantity: Decimal,
+        timestamp: i64,
+    ) -> Result<(), BookError> {
+        if self.orders.contains_key(&order_id) {
+            return Err(BookError::DuplicateOrder { order_id });
+        }
+        if quantity <= dec!(0) {
+            return Err(BookError::InvalidQuantity { order_id, quantity });
+        }
+
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+        self.sequences.insert(order_id, sequence);
+        let queue = self.levels_mut(side).entry(price).or_default();
+        queue.orders.insert(sequence, order_id);
+        queue.total += quantity;
+
+        self.orders.insert(
+            order_id,
+            Order {
+                order_id,
+                side,
+                price,
+                quantity,
+                timestamp,
+            },
+        );
+        self.timestamp = timestamp;
+        Ok(())
+    }
+
+    /// Modify price and/or quantity of a resting order
+    ///
+    /// A quantity reduction at the same price keeps queue priority; a price
+    /// change or quantity increase sends the order to the back of the queue.
+    /// A zero quantity removes the order.
+    ///
+    /// # Errors
+    /// * [`BookError::UnknownOrder`] if the order is not resting
+    /// * [`BookError::InvalidQuantity`] if `quantity` is negative
+    pub fn modify_order(
+        &mut self,
+        order_id: u64,
+        price: Decimal,
+        quantity: Decimal,
+        timestamp: i64,
+    ) -> Result<(), BookError> {
+        let order = self
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or(BookError::UnknownOrder { order_id })?;
+        if quantity < dec!(0) {
+            return Err(BookError::InvalidQuantity { order_id, quantity });
+        }
+
+        if quantity == dec!(0) {
+            self.remove_order(order_id);
+        } else if price == order.price && quantity <= order.quantity {
+            self.reduce_order(order_id, order.quantity - quantity);
+        } else {
+            self.remove_order(order_id);
+            self.add_order(order_id, order.side, price, quantity, timestamp)?;
+        }
+        self.timestamp = timestamp;
+        Ok(())
+    }
+
+    /// Cancel part (`Some(quantity)`) or all (`None`) of a resting order
+    ///
+    /// # Returns
+    /// The quantity actually cancelled
+    ///
+    /// # Errors
+    /// * [`BookError::UnknownOrder`] if the order is not resting
+    /// * [`BookError::InvalidQuantity`] if `quantity` is not positive
+    pub fn cancel_order(
+        &mut self,
+        order_id: u64,
+        quantity: Option<Decimal>,
+        timestamp: i64,
+    ) -> Result<Decimal, BookError> {
+        let resting = self
+            .orders
+            .get(&order_id)
+            .map(|o| o.quantity)
+            .ok_or(BookError::UnknownOrder { order_id })?;
+
+        let cancelled = match quantity {
+            Some(q) if q <= dec!(0) => {
+                return Err(BookError::InvalidQuantity {
+                    order_id,
+                    quantity: q,
+                })
+            }
+            Some(q) => q.min(resting),
+            None => resting,
+        };
+
+        self.reduce_order(order_id, cancelled);
+        self.timestamp = timestamp;
+        Ok(cancelled)
+    }
+
+    /// Execute against a resting order
+    ///
+    /// # Errors
+    /// * [`BookError::UnknownOrder`] if the order is not resting
+    /// * [`BookError::InvalidQuantity`] if `quantity` is not positive or
+    ///   exceeds the resting quantity
+    pub fn execute_order(
+        &mut self,
+        order_id: u64,
+        quantity: Decimal,
+        price: Option<Decimal>,
+        timestamp: i64,
+    ) -> Result<Execution, BookError> {
+        let order = self
+            .orders
+            .get(&order_id)
+            .cloned()
+            .ok_or(BookError::UnknownOrder { order_id })?;
+        if quantity <= dec!(0) || quantity > order.quantity {
+            return Err(BookError::InvalidQuantity { order_id, quantity });
+        }
+
+        self.reduce_order(order_id, quantity);
+        self.timestamp = timestamp;
+
+        Ok(Execution {
+            order_id,
+            side: order.side,
+            price: price.unwrap_or(order.price),
+            quantity,
+            remaining: order.quantity - quantity,
+            timestamp,
+        })
+    }
+
+    /// Reduce an order in place, removing it when nothing is left
+    fn reduce_order(&mut self, order_id: u64, by: Decimal) {
+        let Some(order) = self.orders.get_mut(&order_id) else {
+            return;
+        };
+        if by >= order.quantity {
+            self.remove_order(order_id);
+            return;
+        }
+
+        order.quantity -= by;
+        let (side, price) = (order.side, order.price);
+        if let Some(queue) = self.levels_mut(side).get_mut(&price) {
+            queue.total -= by;
+        }
+    }
+
+    fn remove_order(&mut self, order_id: u64) {
+        let Some(order) = self.orders.remove(&order_id) else {
+            return;
+        };
+        let sequence = self.sequences.remove(&order_id);
+        let levels = self.levels_mut(order.side);
+        if let Some(queue) = levels.get_mut(&order.price) {
+            if let Some(sequence) = sequence {
+                queue.orders.remove(&sequence);
+            }
+            queue.total -= order.quantity;
+            if queue.orders.is_empty() {
+                levels.remove(&order.price);
+            }
+        }
+    }
+
+    fn levels(&self, side: BookSide) -> &BTreeMap<Decimal, PriceQueue> {
+        match side {
+            BookSide::Bid => &self.bids,
+            BookSide::Ask => &self.asks,
+        }
+    }
+
+    fn levels_mut(&mut self, side: BookSide) -> &mut BTreeMap<Decimal, PriceQueue> {
+        match side {
+            BookSide::Bid => &mut self.bids,
+            BookSide::Ask => &mut self.asks,
+        }
+    }
+
+    /// Look up a resting order
+    #[must_use]
+    pub fn order(&self, order_id: u64) -> Option<&Order> {
+        self.orders.get(&order_id)
+    }
+
+    /// Number of resting orders
+    #[must_use]
+    pub fn order_count(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Orders resting at a price, in time priority
+    pub fn queue(&self, side: BookSide, price: Decimal) -> impl Iterator<Item = &Order> + '_ {
+        self.levels(side)
+            .get(&price)
+            .into_iter()
+            .flat_map(|q| q.orders.values())
+            .filter_map(|id| self.orders.get(id))
+    }
+
+    /// Aggregate quantity resting at a price
+    #[must_use]
+    pub fn quantity_at(&self, side: BookSide, price: Decimal) -> Option<Decimal> {
+        self.levels(side).get(&price).map(|q| q.total)
+    }
+
+    /// Best bid price
+    #[must_use]
+    pub fn best_bid(&self) -> Option<Decimal> {
+        self.bids.keys().next_back().copied()
+    }
+
+    /// Best ask price
+    #[must_use]
+    pub fn best_ask(&self) -> Option<Decimal> {
+        self.asks.keys().next().copied()
+    }
+
+    /// Timestamp of the last event applied
+    #[must_use]
+    pub fn timestamp(&self) -> i64 {
+        self.timestamp
+    }
+
+    /// Aggregate into an [`OrderBook`] with the top `depth` levels per side
+    /// (None for all levels)
+    #[must_use]
+    pub fn to_orderbook(&self, depth: Option<usize>) -> OrderBook {
+        let depth = depth.unwrap_or(usize::MAX);
+        let level = |(&price, queue): (&Decimal, &PriceQueue)| Level {
+            price,
+            quantity: queue.total,
+        };
+        OrderBook {
+            bids: self.bids.iter().rev().take(depth).map(level).collect(),
+            asks: self.asks.iter().take(depth).map(level).collect(),
+            timestamp: self.timestamp,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orderbook::{calculate_imbalance, calculate_spread};
+
+    fn sample_book() -> L3Book {
+        let mut book = L3Book::new();
+        book.add_order(1, BookSide::Bid, dec!(100.0), dec!(5), 1)
+            .unwrap();
+        book.add_order(2, BookSide::Bid, dec!(100.0), dec!(3), 2)
+            .unwrap();
+        book.add_order(3, BookSide::Bid, dec!(99.0), dec!(4), 3)
+            .unwrap();
+        book.add_order(4, BookSide::Ask, dec!(101.0), dec!(2), 4)
+            .unwrap();
+        book.add_order(5, BookSide::Ask, dec!(102.0), dec!(6), 5)
+            .unwrap();
+        book
+    }
+
+    fn queue_ids(book: &L3Book, side: BookSide, price: Decimal) -> Vec<u64> {
+        book.queue(side, price).map(|o| o.order_id).collect()
+    }
+
+    #[test]
+    fn test_aggregates_to_orderbook() {
+        let book = sample_book();
+        let view = book.to_orderbook(None);
+
+        assert_eq!(view.bids[0].price, dec!(100.0));
+        assert_eq!(view.bids[0].quantity, dec!(8));
+        assert_eq!(view.bids[1].quantity, dec!(4));
+        assert_eq!(view.asks[0].price, dec!(101.0));
+        assert_eq!(calculate_spread(&view).unwrap().0, dec!(1.0));
+        // (12 - 8) / 20
+        assert_eq!(calculate_imbalance(&view, None), dec!(0.2));
+    }
+
+    #[test]
+    fn test_execute_in_fifo_order() {
+        let mut book = sample_book();
+
+        let exec = book.execute_order(1, dec!(5), None, 10).unwrap();
+        assert_eq!(exec.remaining, dec!(0));
+        assert_eq!(exec.price, dec!(100.0));
+        assert_eq!(exec.to_trade().side, Side::Sell);
+        assert!(book.order(1).is_none());
+        assert_eq!(queue_ids(&book, BookSide::Bid, dec!(100.0)), vec![2]);
+
+        let exec = book
+            .execute_order(2, dec!(1), Some(dec!(100.5)), 11)
+            .unwrap();
+        assert_eq!(exec.remaining, dec!(2));
+        assert_eq!(exec.price, dec!(100.5));
+        assert_eq!(book.quantity_at(BookSide::Bid, dec!(100.0)), Some(dec!(2)));
+
+        assert_eq!(
+            book.execute_order(2, dec!(3), None, 12),
+            Err(BookError::InvalidQuantity {
+                order_id: 2,
+                quantity: dec!(3)
+            })
+        );
+    }
+
+    #[test]
+    fn test_modify_priority() {
+        let mut book = sample_book();
+
+        // Size down keeps priority
+        book.modify_order(1, dec!(100.0), dec!(4), 10).unwrap();
+        assert_eq!(queue_ids(&book, BookSide::Bid, dec!(100.0)), vec![1, 2]);
+        assert_eq!(book.quantity_at(BookSide::Bid, dec!(100.0)), Some(dec!(7)));
+
+        // Size up loses priority
+        book.modify_order(1, dec!(100.0), dec!(6), 11).unwrap();
+        assert_eq!(queue_ids(&book, BookSide::Bid, dec!(100.0)), vec![2, 1]);
+
+        // Price change moves the order
+        book.modify_order(2, dec!(99.0), dec!(3), 12).unwrap();
+        assert_eq!(queue_ids(&book, BookSide::Bid, dec!(99.0)), vec![3, 2]);
+        assert_eq!(book.quantity_at(BookSide::Bid, dec!(100.0)), Some(dec!(6)));
+    }
+
+    #[test]
+    fn test_cancel() {
+        let mut book = sample_book();
+
+        assert_eq!(book.cancel_order(5, Some(dec!(2)), 10), Ok(dec!(2)));
+        assert_eq!(book.quantity_at(BookSide::Ask, dec!(102.0)), Some(dec!(4)));
+
+        assert_eq!(book.cancel_order(4, None, 11), Ok(dec!(2)));
+        assert_eq!(book.best_ask(), Some(dec!(102.0)));
+        assert_eq!(book.quantity_at(BookSide::Ask, dec!(101.0)), None);
+
+        assert_eq!(
+            book.cancel_order(4, None, 12),
+            Err(BookError::UnknownOrder { order_id: 4 })
+        );
+    }
+
+    #[test]
+    fn test_cancel_from_busy_level() {
+        let mut book = L3Book::new();
+        for id in 1..=1000 {
+            book.add_order(id, BookSide::Bid, dec!(100.0), dec!(1), 0)
+                .unwrap();
+        }
+        for id in (2..=1000).step_by(2) {
+            book.cancel_order(id, None, 1).unwrap();
+        }
+
+        let odd: Vec<u64> = (1..=1000).step_by(2).collect();
+        assert_eq!(queue_ids(&book, BookSide::Bid, dec!(100.0)), odd);
+        assert_eq!(
+            book.quantity_at(BookSide::Bid, dec!(100.0)),
+            Some(dec!(500))
+        );
+    }
+
+    #[test]
+    fn test_apply_events() {
+        let mut book = L3Book::new();
+        let events = [
+            OrderEvent::Add {
+                order_id: 7,
+                side: BookSide::Ask,
+                price: dec!(50.0),
+                quantity: dec!(10),
+                timestamp: 1,
+            },
+            OrderEvent::Execute {
+                order_id: 7,
+                quantity: dec!(4),
+                price: None,
+                timestamp: 2,
+            },
+        ];
+
+        assert_eq!(book.apply(&events[0]), Ok(None));
+        let exec = book.apply(&events[1]).unwrap().unwrap();
+        assert_eq!(exec.to_trade().side, Side::Buy);
+        assert_eq!(book.quantity_at(BookSide::Ask, dec!(50.0)), Some(dec!(6)));
+        assert_eq!(
+            book.apply(&events[0]),
+            Err(BookError::DuplicateOrder { order_id: 7 })
+        );
+    }
+}
@@ -2,6 +2,25 @@
 //!
 //! This module provides functionality for analyzing order book data,
 //! including spread calculation, imbalance detection, and depth analysis.
+//! [`L2Book`] maintains a book incrementally from snapshots and sequenced
+//! level updates, reporting feed gaps as [`BookError`]s. [`L3Book`] tracks
+//! individual orders and aggregates down to the same [`OrderBook`] shape.
+//! [`level_updates`] derives an update stream from consecutive snapshots.
+//! [`aggregate_ofi`] measures order flow imbalance across snapshots.
+//! [`simulate_market_order`] walks the book to estimate market impact.
+
+mod impact;
+mod l2;
+mod l3;
+mod ofi;
+
+pub use impact::{max_size_within_bps, simulate_market_order, MarketImpact, SlippageReference};
+pub use l2::{level_updates, BookError, BookSide, L2Book, LevelUpdate};
+pub use l3::{Execution, L3Book, Order, OrderEvent};
+pub use ofi::{
+    aggregate_ofi, calculate_multi_level_ofi, calculate_ofi, estimate_ofi_impact, OfiImpact,
+    OfiWindow,
+};
 
 use crate::types::{Level, OrderBook};
 use rust_decimal::Decimal;
@@ -77,6 +96,20 @@ pub fn mid_price(orderbook: &OrderBook) -> Option<Decimal> {
     }
 }
 
+/// Find the latest snapshot at or before `timestamp`
+///
+/// # Arguments
+/// * `books` - Snapshots sorted by timestamp
+/// * `timestamp` - Time of interest
+///
+/// # Returns
+/// The prevailing snapshot, or None if every snapshot is later
+#[must_use]
+pub fn prevailing_book(books: &[OrderBook], timestamp: i64) -> Option<&OrderBook> {
+    let index = books.partition_point(|b| b.timestamp <= timestamp);
+    index.checked_sub(1).map(|i| &books[i])
+}
+
 /// Calculate total volume at a specific depth
 #[must_use]
 pub fn total_volume(levels: &[Level], depth: Option<usize>) -> Decimal {
@@ -132,6 +165,25 @@ mod tests {
         assert!(spread_pct > dec!(0.0019) && spread_pct < dec!(0.0021));
     }
 
+    #[test]
+    fn test_prevailing_book() {
+        let books: Vec<OrderBook> = [10, 20, 20, 30]
+            .into_iter()
+            .map(|timestamp| OrderBook {
+                timestamp,
+                ..sample_orderbook()
+            })
+            .collect();
+
+        assert!(prevailing_book(&books, 9).is_none());
+        assert_eq!(prevailing_book(&books, 10).map(|b| b.timestamp), Some(10));
+        assert!(std::ptr::eq(
+            prevailing_book(&books, 25).unwrap(),
+            &books[2]
+        ));
+        assert_eq!(prevailing_book(&books, 99).map(|b| b.timestamp), Some(30));
+    }
+
     #[test]
     fn test_calculate_spread_empty() {
         let empty_ob = OrderBook {
@@ -0,0 +1,339 @@
+//! Order Flow Imbalance (Cont, Kukanov and Stoikov)
+//!
+//! OFI measures the net change in resting liquidity between two snapshots.
+//! Bid size that appears at the same or a higher price adds buying
+//! pressure, and bid size that leaves from the same or a higher previous
+//! price removes it; the ask side mirrors this. The multi-level variant
+//! applies the same rule to each of the top N levels.
+
+use super::mid_price;
+use crate::stats::ols;
+use crate::types::{Level, OrderBook};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Contribution of one bid level
+///
+/// A missing level behaves as if it sat at an infinitely low price.
+fn bid_flow(previous: Option<&Level>, current: Option<&Level>) -> Decimal {
+    match (previous, current) {
+        (Some(p), Some(c)) => {
+            let mut flow = dec!(0);
+            if c.price >= p.price {
+                flow += c.quantity;
+            }
+            if c.price <= p.price {
+                flow -= p.quantity;
+            }
+            flow
+        }
+        (None, Some(c)) => c.quantity,
+        (Some(p), None) => -p.quantity,
+        (None, None) => dec!(0),
+    }
+}
+
+/// Contribution of one ask level (positive when ask liquidity leaves)
+///
+/// A missing level behaves as if it sat at an infinitely high price.
+fn ask_flow(previous: Option<&Level>, current: Option<&Level>) -> Decimal {
+    match (previous, current) {
+        (Some(p), Some(c)) => {
+            let mut flow = dec!(0);
+            if c.price <= p.price {
+                flow -= c.quantity;
+            }
+            if c.price >= p.price {
+                flow += p.quantity;
+            }
+            flow
+        }
+        (None, Some(c)) => -c.quantity,
+        (Some(p), None) => p.quantity,
+        (None, None) => dec!(0),
+    }
+}
+
+/// Calculate best-level Order Flow Imbalance between two snapshots
+///
+/// # Arguments
+/// * `previous` - Earlier snapshot
+/// * `current` - Later snapshot
+///
+/// # Returns
+/// OFI (positive = net buying pressure)
+#[must_use]
+pub fn calculate_ofi(previous: &OrderBook, current: &OrderBook) -> Decimal {
+    bid_flow(previous.bids.first(), current.bids.first())
+        + ask_flow(previous.asks.first(), current.asks.first())
+}
+
+/// Calculate Order Flow Imbalance for each of the top `levels` levels
+///
+/// # Arguments
+/// * `previous` - Earlier snapshot
+/// * `current` - Later snapshot
+/// * `levels` - Number of levels per side
+///
+/// # Returns
+/// One OFI value per level, best level first
+#[must_use]
+pub fn calculate_multi_level_ofi(
+    previous: &OrderBook,
+    current: &OrderBook,
+    levels: usize,
+) -> Vec<Decimal> {
+    (0..levels)
+        .map(|i| {
+            bid_flow(previous.bids.get(i), current.bids.get(i))
+                + ask_flow(previous.asks.get(i), current.asks.get(i))
+        })
+        .collect()
+}
+
+/// OFI accumulated over one time window
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfiWindow {
+    /// Window start (aligned to a multiple of the window length)
+    pub start: i64,
+    /// Window end (exclusive)
+    pub end: i64,
+    /// OFI per level, best level first
+    pub ofi: Vec<Decimal>,
+    /// Mid-price change over the window's snapshot transitions
+    pub mid_change: Decimal,
+    /// Number of snapshot transitions in the window
+    pub updates: usize,
+}
+
+impl OfiWindow {
+    /// Best-level OFI
+    #[must_use]
+    pub fn best(&self) -> Decimal {
+        self.ofi.first().copied().unwrap_or_default()
+    }
+
+    /// OFI summed over all levels
+    #[must_use]
+    pub fn integrated(&self) -> Decimal {
+        self.ofi.iter().sum()
+    }
+}
+
+/// Aggregate OFI over fixed time windows
+///
+/// Each transition between consecutive snapshots is assigned to the window
+/// containing the later snapshot. Windows without transitions are skipped.
+/// Transitions where either snapshot lacks a two-sided quote add nothing to
+/// the mid change.
+///
+/// # Arguments
+/// * `books` - Snapshots sorted by timestamp
+/// * `window` - Window length in the snapshots' timestamp unit
+/// * `levels` - Number of levels per side (1 for best-level OFI)
+///
+/// # Returns
+/// Vector of OfiWindow in time order
+#[must_use]
+pub fn aggregate_ofi(books: &[OrderBook], window: i64, levels: usize) -> Vec<OfiWindow> {
+    let window = window.max(1);
+    let levels = levels.max(1);
+    let mut windows: Vec<OfiWindow> = Vec::new();
+
+    for pair in books.windows(2) {
+        let (previous, current) = (&pair[0], &pair[1]);
+        let start = current.timestamp.div_euclid(window) * window;
+        let ofi = calculate_multi_level_ofi(previous, current, levels);
+        let mid_change = match (mid_price(previous), mid_price(current)) {
+            (Some(before), Some(after)) => after - before,
+            _ => dec!(0),
+        };
+
+        match windows.last_mut() {
+            Some(w) if w.start == start => {
+                for (total, value) in w.ofi.iter_mut().zip(ofi) {
+                    *total += value;
+                }
+                w.mid_change += mid_change;
+                w.updates += 1;
+            }
+            _ => windows.push(OfiWindow {
+                start,
+                end: start + window,
+                ofi,
+                mid_change,
+                updates: 1,
+            }),
+        }
+    }
+
+    windows
+}
+
+/// Linear price impact of OFI
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OfiImpact {
+    pub intercept: Decimal,
+    /// Impact coefficient per level, best level first
+    pub coefficients: Vec<Decimal>,
+    pub r_squared: Decimal,
+    pub samples: usize,
+}
+
+/// Regress window mid-price changes on per-level OFI
+///
+/// Fits `mid_change = intercept + sum(coefficient[i] * ofi[i])` by least
+/// squares. With single-level windows the coefficient is the price impact
+/// per unit of OFI.
+///
+/// # Arguments
+/// * `windows` - Output of [`aggregate_ofi`]
+///
+/// # Returns
+/// The fit, or None with too few windows or collinear levels
+#[must_use]
+pub fn estimate_ofi_impact(windows: &[OfiWindow]) -> Option<OfiImpact> {
+    let y: Vec<f64> = windows
+        .iter()
+        .map(|w| w.mid_change.to_f64().unwrap_or(0.0))
+        .collect();
+    let x: Vec<Vec<f64>> = windows
+        .iter()
+        .map(|w| w.ofi.iter().map(|v| v.to_f64().unwrap_or(0.0)).collect())
+        .collect();
+    let fit = ols(&y, &x)?;
+
+    let coefficients = fit
+        .coefficients
+        .iter()
+        .map(|&c| Decimal::from_f64(c))
+        .collect::<Option<Vec<_>>>()?;
+    Some(OfiImpact {
+        intercept: coefficients[0],
+        coefficients: coefficients[1..].to_vec(),
+        r_squared: Decimal::from_f64(fit.r_squared)?,
+        samples: fit.samples,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn book(levels: &[(Decimal, Decimal, Decimal, Decimal)], timestamp: i64) -> OrderBook {
+        OrderBook {
+            bids: levels
+                .iter()
+                .map(|&(price, quantity, _, _)| Level { price, quantity })
+                .collect(),
+            asks: levels
+                .iter()
+                .map(|&(_, _, price, quantity)| Level { price, quantity })
+                .collect(),
+            timestamp,
+        }
+    }
+
+    fn top(bid: Decimal, bid_qty: Decimal, ask: Decimal, ask_qty: Decimal, ts: i64) -> OrderBook {
+        book(&[(bid, bid_qty, ask, ask_qty)], ts)
+    }
+
+    #[test]
+    fn test_best_level_ofi() {
+        let base = top(dec!(100), dec!(5), dec!(101), dec!(5), 0);
+
+        // Bid size grows at the same price
+        let more_bid = top(dec!(100), dec!(8), dec!(101), dec!(5), 1);
+        assert_eq!(calculate_ofi(&base, &more_bid), dec!(3));
+
+        // Bid steps up: all new size counts
+        let better_bid = top(dec!(100.5), dec!(2), dec!(101), dec!(5), 1);
+        assert_eq!(calculate_ofi(&base, &better_bid), dec!(2));
+
+        // Bid drops out: all old size leaves
+        let worse_bid = top(dec!(99), dec!(7), dec!(101), dec!(5), 1);
+        assert_eq!(calculate_ofi(&base, &worse_bid), dec!(-5));
+
+        // Ask lifted away: buying pressure
+        let ask_up = top(dec!(100), dec!(5), dec!(102), dec!(4), 1);
+        assert_eq!(calculate_ofi(&base, &ask_up), dec!(5));
+
+        // Ask size added at the same price: selling pressure
+        let more_ask = top(dec!(100), dec!(5), dec!(101), dec!(9), 1);
+        assert_eq!(calculate_ofi(&base, &more_ask), dec!(-4));
+    }
+
+    #[test]
+    fn test_multi_level_ofi() {
+        let previous = book(
+            &[
+                (dec!(100), dec!(5), dec!(101), dec!(5)),
+                (dec!(99), dec!(5), dec!(102), dec!(5)),
+            ],
+            0,
+        );
+        let current = book(&[(dec!(100), dec!(6), dec!(101), dec!(5))], 1);
+
+        let ofi = calculate_multi_level_ofi(&previous, &current, 3);
+        assert_eq!(ofi, vec![dec!(1), dec!(0), dec!(0)]);
+        assert_eq!(ofi[0], calculate_ofi(&previous, &current));
+    }
+
+    #[test]
+    fn test_aggregate_ofi() {
+        // Quotes shift by half a step each update, keeping 10 on each side
+        let mut books = vec![top(dec!(100), dec!(10), dec!(101), dec!(10), 0)];
+        for (i, step) in [1, -1, 2, 1, -2, 1].into_iter().enumerate() {
+            let last = &books[books.len() - 1];
+            let shift = Decimal::from(step) / dec!(2);
+            let next = top(
+                last.bids[0].price + shift,
+                dec!(10),
+                last.asks[0].price + shift,
+                dec!(10),
+                (i as i64 + 1) * 10,
+            );
+            books.push(next);
+        }
+
+        let windows = aggregate_ofi(&books, 10, 1);
+        assert_eq!(windows.len(), 6);
+        assert_eq!((windows[0].start, windows[0].end), (10, 20));
+        assert_eq!(windows[0].best(), dec!(20));
+        assert_eq!(windows[0].mid_change, dec!(0.5));
+        assert_eq!(windows[1].best(), dec!(-20));
+
+        let coarse = aggregate_ofi(&books, 30, 1);
+        let updates: Vec<usize> = coarse.iter().map(|w| w.updates).collect();
+        assert_eq!(updates, vec![2, 3, 1]);
+        let total: Decimal = windows.iter().map(OfiWindow::best).sum();
+        assert_eq!(
+            coarse.iter().map(OfiWindow::integrated).sum::<Decimal>(),
+            total
+        );
+    }
+
+    #[test]
+    fn test_ofi_impact() {
+        let windows: Vec<OfiWindow> = [(10, 0.5), (-5, -0.2), (20, 1.1), (0, 0.0), (-10, -0.5)]
+            .into_iter()
+            .enumerate()
+            .map(|(i, (ofi, change))| OfiWindow {
+                start: i as i64,
+                end: i as i64 + 1,
+                ofi: vec![Decimal::from(ofi)],
+                mid_change: Decimal::from_f64(change).unwrap(),
+                updates: 1,
+            })
+            .collect();
+
+        let impact = estimate_ofi_impact(&windows).unwrap();
+        assert_eq!(impact.samples, 5);
+        assert_eq!(impact.coefficients.len(), 1);
+        assert!((impact.coefficients[0] - dec!(0.05)).abs() < dec!(0.01));
+        assert!(impact.r_squared > dec!(0.95));
+        assert!(estimate_ofi_impact(&windows[..2]).is_none());
+    }
+}
@@ -0,0 +1,217 @@
+//! Iceberg Detection from Book Replenishment
+//!
+//! An iceberg shows only its peak. When trades take out the displayed
+//! quantity, the level refills from the hidden reserve instead of
+//! disappearing. Correlating the trades between consecutive snapshots with
+//! the visible quantity at each level finds levels that keep refilling
+//! after being traded through.
+
+use crate::orderbook::BookSide;
+use crate::types::{OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+/// A level that refilled after being traded through
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IcebergDetection {
+    pub side: BookSide,
+    pub price: Decimal,
+    /// Snapshot before the first traded-through interval
+    pub first_seen: i64,
+    /// Snapshot of the latest refill
+    pub last_seen: i64,
+    pub refills: usize,
+    /// Average displayed quantity after a refill
+    pub peak_size: Decimal,
+    /// Volume traded at the level since it was first traded through
+    pub executed: Decimal,
+    /// Executed volume beyond the quantity displayed at `first_seen`
+    pub hidden_executed: Decimal,
+}
+
+/// Refill history of one level
+#[derive(Debug, Clone)]
+struct Tracked {
+    first_seen: i64,
+    last_seen: i64,
+    initial_visible: Decimal,
+    peaks: Vec<Decimal>,
+    executed: Decimal,
+}
+
+impl Tracked {
+    fn detection(&self, side: BookSide, price: Decimal) -> IcebergDetection {
+        let refills = self.peaks.len();
+        IcebergDetection {
+            side,
+            price,
+            first_seen: self.first_seen,
+            last_seen: self.last_seen,
+            refills,
+            peak_size: self.peaks.iter().sum::<Decimal>() / Decimal::from(refills.max(1)),
+            executed: self.executed,
+            hidden_executed: (self.executed - self.initial_visible).max(dec!(0)),
+        }
+    }
+}
+
+/// Detect icebergs from level replenishment
+///
+/// An interval between two snapshots is a refill at a level when the
+/// volume traded there (by sellers for bids, by buyers for asks, or by
+/// unknown aggressors) is at least the quantity displayed before, and the
+/// level is still displayed after. A level stops being tracked when it is
+/// traded through and does not refill.
+///
+/// # Arguments
+/// * `books` - Consecutive snapshots sorted by timestamp
+/// * `trades` - Trades over the same period, sorted by timestamp
+/// * `min_refills` - Minimum refills to report a level
+///
+/// # Returns
+/// Vector of detections, sorted by first_seen
+#[must_use]
+pub fn detect_iceberg_replenishment(
+    books: &[OrderBook],
+    trades: &[Trade],
+    min_refills: usize,
+) -> Vec<IcebergDetection> {
+    let mut tracked: HashMap<(BookSide, Decimal), Tracked> = HashMap::new();
+    let mut detections = Vec::new();
+    let mut finish = |side: BookSide, price: Decimal, t: Tracked| {
+        if t.peaks.len() >= min_refills.max(1) {
+            detections.push(t.detection(side, price));
+        }
+    };
+
+    for pair in books.windows(2) {
+        let (prev, cur) = (&pair[0], &pair[1]);
+        let start = trades.partition_point(|t| t.timestamp <= prev.timestamp);
+        let end = trades.partition_point(|t| t.timestamp <= cur.timestamp);
+
+        for (side, before, after) in [
+            (BookSide::Bid, &prev.bids, &cur.bids),
+            (BookSide::Ask, &prev.asks, &cur.asks),
+        ] {
+            // Bids are hit by sellers and asks lifted by buyers
+            let same_side = match side {
+                BookSide::Bid => Side::Buy,
+                BookSide::Ask => Side::Sell,
+            };
+            let mut traded: BTreeMap<Decimal, Decimal> = BTreeMap::new();
+            for trade in &trades[start..end] {
+                if trade.side != same_side {
+                    *traded.entry(trade.price).or_default() += trade.quantity;
+                }
+            }
+            let after: HashMap<Decimal, Decimal> =
+                after.iter().map(|l| (l.price, l.quantity)).collect();
+
+            for level in before {
+                let key = (side, level.price);
+                let volume = traded.get(&level.price).copied().unwrap_or_default();
+                let visible = after.get(&level.price).copied().unwrap_or_default();
+
+                if let Some(t) = tracked.get_mut(&key) {
+                    t.executed += volume;
+                }
+                if volume <= dec!(0) || volume < level.quantity {
+                    continue;
+                }
+
+                if visible > dec!(0) {
+                    let t = tracked.entry(key).or_insert_with(|| Tracked {
+                        first_seen: prev.timestamp,
+                        last_seen: cur.timestamp,
+                        initial_visible: level.quantity,
+                        peaks: Vec::new(),
+                        executed: volume,
+                    });
+                    t.last_seen = cur.timestamp;
+                    t.peaks.push(visible);
+                } else if let Some(t) = tracked.remove(&key) {
+                    finish(side, level.price, t);
+                }
+            }
+        }
+    }
+
+    for ((side, price), t) in tracked {
+        finish(side, price, t);
+    }
+    detections.sort_by_key(|d| (d.first_seen, d.price));
+    detections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Level;
+
+    fn book(bid: (Decimal, Decimal), ask: (Decimal, Decimal), timestamp: i64) -> OrderBook {
+        OrderBook {
+            bids: vec![Level {
+                price: bid.0,
+                quantity: bid.1,
+            }],
+            asks: vec![Level {
+                price: ask.0,
+                quantity: ask.1,
+            }],
+            timestamp,
+        }
+    }
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_refilling_bid() {
+        // The bid at 100 shows 5 and refills every time sellers take it out
+        let books: Vec<OrderBook> = (0..4)
+            .map(|i| book((dec!(100), dec!(5)), (dec!(101), dec!(2)), i))
+            .collect();
+        let trades: Vec<Trade> = (1..4)
+            .flat_map(|i| {
+                [
+                    trade(dec!(100), dec!(3), Side::Sell, i),
+                    trade(dec!(100), dec!(3), Side::Sell, i),
+                ]
+            })
+            .collect();
+
+        let detections = detect_iceberg_replenishment(&books, &trades, 2);
+        assert_eq!(detections.len(), 1);
+        let d = &detections[0];
+        assert_eq!(d.side, BookSide::Bid);
+        assert_eq!(d.price, dec!(100));
+        assert_eq!((d.first_seen, d.last_seen), (0, 3));
+        assert_eq!(d.refills, 3);
+        assert_eq!(d.peak_size, dec!(5));
+        assert_eq!(d.executed, dec!(18));
+        assert_eq!(d.hidden_executed, dec!(13));
+    }
+
+    #[test]
+    fn test_plain_level_is_not_iceberg() {
+        // The ask is lifted once and the price moves up
+        let books = vec![
+            book((dec!(100), dec!(5)), (dec!(101), dec!(2)), 0),
+            book((dec!(100), dec!(5)), (dec!(102), dec!(2)), 1),
+        ];
+        let trades = vec![
+            trade(dec!(101), dec!(2), Side::Buy, 1),
+            // Buyers at the bid do not consume it
+            trade(dec!(100), dec!(9), Side::Buy, 1),
+        ];
+        assert!(detect_iceberg_replenishment(&books, &trades, 1).is_empty());
+    }
+}
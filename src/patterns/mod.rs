@@ -1,7 +1,24 @@
 //! Pattern Detection Module
 //!
 //! This module provides functionality to detect common market microstructure patterns.
-
+//! [`detect_spoofing_history`] finds spoofing and layering in a sequence of
+//! book snapshots rather than a single one, and
+//! [`detect_iceberg_replenishment`] finds icebergs from levels that refill
+//! after being traded through. [`detect_quote_stuffing`] flags bursts of
+//! book updates far above their baseline rate. [`detect_wash_trading`] flags
+//! wash trades, round trips and self trades in the tape.
+
+mod iceberg;
+mod spoofing;
+mod stuffing;
+mod wash;
+
+pub use iceberg::{detect_iceberg_replenishment, IcebergDetection};
+pub use spoofing::{detect_spoofing_history, SpoofingConfig, SpoofingDetection, SpoofingKind};
+pub use stuffing::{detect_quote_stuffing, QuoteStuffingConfig, QuoteStuffingEvent};
+pub use wash::{detect_wash_trading, WashTradingConfig};
+
+use crate::orderbook::BookSide;
 use crate::types::{OrderBook, Trade};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
@@ -15,13 +32,39 @@ pub enum Pattern {
         estimated_size: Decimal,
     },
     /// Potential spoofing detected
-    Spoofing { price: Decimal, side: String },
+    Spoofing { price: Decimal, side: BookSide },
     /// Strong support level
     Support { price: Decimal, strength: Decimal },
     /// Strong resistance level
     Resistance { price: Decimal, strength: Decimal },
-    /// Liquidity absorption detected
-    Absorption { price: Decimal, volume: Decimal },
+    /// Liquidity absorption detected (`timestamp` of the first absorbed trade)
+    Absorption {
+        price: Decimal,
+        volume: Decimal,
+        timestamp: i64,
+    },
+    /// Repeated opposite-side trades of identical price and size
+    /// (`trades` are indices into the input slice)
+    WashTrade {
+        price: Decimal,
+        quantity: Decimal,
+        trades: Vec<usize>,
+    },
+    /// An account's trades netting to zero at an unchanged price (`volume`
+    /// is the quantity bought)
+    RoundTrip {
+        price: Decimal,
+        volume: Decimal,
+        account: String,
+        trades: Vec<usize>,
+    },
+    /// Buyer and seller are the same account
+    SelfTrade {
+        price: Decimal,
+        quantity: Decimal,
+        account: String,
+        trades: Vec<usize>,
+    },
 }
 
 /// Detect potential iceberg orders
@@ -29,6 +72,10 @@ pub enum Pattern {
 /// Iceberg orders are large orders hidden by placing small visible amounts
 /// Detection: Multiple fills at same price with consistent size
 ///
+/// Trades alone miss most icebergs and flag normal trading; with book
+/// snapshots, [`detect_iceberg_replenishment`] is preferred and is what the
+/// CLI runs by default (this one needs `--min-fills`).
+///
 /// # Arguments
 /// * `trades` - Recent trades
 /// * `min_fills` - Minimum number of fills to consider
@@ -79,6 +126,11 @@ pub fn detect_iceberg_orders(
 /// Spoofing: Large orders on one side that get cancelled, manipulating price
 /// Detection: Large orders far from mid that don't get filled
 ///
+/// A single snapshot cannot show an order being pulled, so this flags any
+/// large resting level; [`detect_spoofing_history`] tracks placement and
+/// cancellation over a book history and is what the CLI runs by default
+/// (this one needs `--snapshot-spoof-threshold`).
+///
 /// # Arguments
 /// * `orderbook` - Current order book
 /// * `threshold` - Minimum size to consider as potential spoof
@@ -95,7 +147,7 @@ pub fn detect_spoofing(orderbook: &OrderBook, threshold: Decimal) -> Vec<Pattern
             // Large order not at best bid might be spoofing
             patterns.push(Pattern::Spoofing {
                 price: bid.price,
-                side: "bid".to_string(),
+                side: BookSide::Bid,
             });
         }
     }
@@ -105,7 +157,7 @@ pub fn detect_spoofing(orderbook: &OrderBook, threshold: Decimal) -> Vec<Pattern
             // Large order not at best ask might be spoofing
             patterns.push(Pattern::Spoofing {
                 price: ask.price,
-                side: "ask".to_string(),
+                side: BookSide::Ask,
             });
         }
     }
@@ -180,6 +232,7 @@ pub fn detect_absorption(
         patterns.push(Pattern::Absorption {
             price: avg_price,
             volume: total_volume,
+            timestamp: trades[0].timestamp,
         });
     }
 
@@ -189,7 +242,7 @@ pub fn detect_absorption(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::Level;
+    use crate::types::{Level, Side};
 
     #[test]
     fn test_detect_iceberg_orders() {
@@ -197,31 +250,31 @@ mod tests {
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.1),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1000,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.1),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1001,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.1),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1002,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.1),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1003,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(0.1),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1004,
             },
         ];
@@ -266,7 +319,7 @@ mod tests {
 
         if let Pattern::Spoofing { price, side } = &patterns[0] {
             assert_eq!(*price, dec!(49999.0));
-            assert_eq!(side, "bid");
+            assert_eq!(*side, BookSide::Bid);
         } else {
             panic!("Expected Spoofing pattern");
         }
@@ -310,19 +363,19 @@ mod tests {
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(5.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1000,
             },
             Trade {
                 price: dec!(50000.5),
                 quantity: dec!(5.0),
-                side: "sell".to_string(),
+                side: Side::Sell,
                 timestamp: 1001,
             },
             Trade {
                 price: dec!(50000.2),
                 quantity: dec!(5.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1002,
             },
         ];
@@ -0,0 +1,490 @@
+//! Time-Aware Spoofing and Layering Detection
+//!
+//! Watches a sequence of book snapshots together with the trades between
+//! them. A large quantity that appears away from the touch and is removed
+//! without trading within a short lifetime is a cancelled candidate. It is
+//! flagged as spoofing when the price came close to it before the cancel or
+//! when the opposite side of the book traded while it rested. Several
+//! candidates on one side placed and pulled together are flagged as
+//! layering whether or not either trigger fired.
+
+use crate::orderbook::BookSide;
+use crate::types::{Level, OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Thresholds for [`detect_spoofing_history`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpoofingConfig {
+    /// Minimum quantity added at a level to track it
+    pub min_size: Decimal,
+    /// Minimum distance from the same-side touch when placed
+    pub min_distance: Decimal,
+    /// Distance from the touch that counts as the price reaching the order
+    pub approach_distance: Decimal,
+    /// Longest lifetime still considered, in the books' timestamp unit
+    pub max_lifetime: i64,
+    /// Minimum levels placed and pulled together to flag layering
+    pub layering_min_levels: usize,
+    /// Maximum spread of placement (and of cancel) times within a layer
+    pub layering_window: i64,
+}
+
+impl SpoofingConfig {
+    /// Orders placed at least two ticks away, reached within one tick, and
+    /// cancelled within 5 time units; layers of three levels within 1 unit
+    #[must_use]
+    pub fn new(min_size: Decimal, tick_size: Decimal) -> Self {
+        Self {
+            min_size,
+            min_distance: tick_size * dec!(2),
+            approach_distance: tick_size,
+            max_lifetime: 5,
+            layering_min_levels: 3,
+            layering_window: 1,
+        }
+    }
+}
+
+/// Kind of manipulation detected
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpoofingKind {
+    /// A single large order
+    Spoof,
+    /// Several stacked levels placed and pulled together
+    Layering,
+}
+
+/// A detected spoof or layer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SpoofingDetection {
+    pub kind: SpoofingKind,
+    pub side: BookSide,
+    /// Level prices involved, best first
+    pub prices: Vec<Decimal>,
+    /// Total quantity placed and cancelled
+    pub size: Decimal,
+    pub placed_at: i64,
+    pub cancelled_at: i64,
+    pub lifetime: i64,
+    /// The same-side touch came within `approach_distance` before the cancel
+    pub price_approached: bool,
+    /// Volume traded against the opposite side of the book while resting
+    /// (buyer-initiated for bid spoofs, seller-initiated for ask spoofs)
+    pub opposite_volume: Decimal,
+    /// Score in [0, 1] from the lifetime, relative size and triggers
+    pub confidence: Decimal,
+}
+
+/// A large quantity being tracked at one level
+#[derive(Debug, Clone)]
+struct Candidate {
+    side: BookSide,
+    price: Decimal,
+    size: Decimal,
+    placed_at: i64,
+    /// Same-side touch quantity when placed
+    touch_quantity: Decimal,
+    approached: bool,
+}
+
+fn ladder(levels: &[Level]) -> BTreeMap<Decimal, Decimal> {
+    levels.iter().map(|l| (l.price, l.quantity)).collect()
+}
+
+/// Best level of a ladder: the highest bid or the lowest ask
+fn best(side: BookSide, ladder: &BTreeMap<Decimal, Decimal>) -> Option<(&Decimal, &Decimal)> {
+    match side {
+        BookSide::Bid => ladder.last_key_value(),
+        BookSide::Ask => ladder.first_key_value(),
+    }
+}
+
+/// Distance from the same-side touch (positive away from the spread)
+fn distance_from_touch(side: BookSide, price: Decimal, touch: Decimal) -> Decimal {
+    match side {
+        BookSide::Bid => touch - price,
+        BookSide::Ask => price - touch,
+    }
+}
+
+/// Volume traded against the opposite side of the book with
+/// `from <= timestamp <= to`: buyer-initiated for bids, seller-initiated
+/// for asks
+fn traded_volume(trades: &[Trade], side: BookSide, from: i64, to: i64) -> Decimal {
+    let aggressor = match side {
+        BookSide::Bid => Side::Buy,
+        BookSide::Ask => Side::Sell,
+    };
+    let start = trades.partition_point(|t| t.timestamp < from);
+    trades[start..]
+        .iter()
+        .take_while(|t| t.timestamp <= to)
+        .filter(|t| t.side == aggressor)
+        .map(|t| t.quantity)
+        .sum()
+}
+
+/// Cancelled candidate before classification
+struct Cancelled {
+    candidate: Candidate,
+    cancelled_at: i64,
+}
+
+fn score(
+    config: &SpoofingConfig,
+    lifetime: i64,
+    size: Decimal,
+    touch_quantity: Decimal,
+    approached: bool,
+    opposite_volume: Decimal,
+) -> Decimal {
+    let lifetime_score =
+        dec!(1) - Decimal::from(lifetime) / Decimal::from(config.max_lifetime.max(0) + 1);
+    let size_score = size / (size + touch_quantity);
+    let mut trigger_score = dec!(0);
+    if approached {
+        trigger_score += dec!(0.5);
+    }
+    if opposite_volume > dec!(0) {
+        trigger_score += dec!(0.5);
+    }
+    ((lifetime_score + size_score + trigger_score) / dec!(3)).round_dp(4)
+}
+
+/// Detect spoofing and layering over a book history
+///
+/// Level quantity removed between two snapshots beyond what traded at that
+/// price in between counts as cancelled. A tracked order is dropped when
+/// half of it trades, and cancelled when 80% of it is pulled.
+///
+/// # Arguments
+/// * `books` - Consecutive snapshots sorted by timestamp
+/// * `trades` - Trades over the same period, sorted by timestamp
+/// * `config` - Detection thresholds
+///
+/// # Returns
+/// Detections sorted by cancel time; orders that are part of a layer are
+/// only reported in the layer
+#[must_use]
+pub fn detect_spoofing_history(
+    books: &[OrderBook],
+    trades: &[Trade],
+    config: &SpoofingConfig,
+) -> Vec<SpoofingDetection> {
+    let mut open: HashMap<(BookSide, Decimal), Candidate> = HashMap::new();
+    let mut cancelled = Vec::new();
+
+    for pair in books.windows(2) {
+        let (prev, cur) = (&pair[0], &pair[1]);
+        let start = trades.partition_point(|t| t.timestamp <= prev.timestamp);
+        let end = trades.partition_point(|t| t.timestamp <= cur.timestamp);
+        let interval = &trades[start..end];
+
+        for (side, before, after) in [
+            (BookSide::Bid, &prev.bids, &cur.bids),
+            (BookSide::Ask, &prev.asks, &cur.asks),
+        ] {
+            let (before, after) = (ladder(before), ladder(after));
+            let touch = best(side, &after).map(|(&p, &q)| (p, q));
+            let prev_touch = best(side, &before).map(|(&p, _)| p);
+            let prices: BTreeSet<Decimal> = before.keys().chain(after.keys()).copied().collect();
+
+            for price in prices {
+                let old = before.get(&price).copied().unwrap_or_default();
+                let new = after.get(&price).copied().unwrap_or_default();
+                let key = (side, price);
+
+                if let Some(candidate) = open.get_mut(&key) {
+                    if let Some(touch) = prev_touch {
+                        if distance_from_touch(side, price, touch) <= config.approach_distance {
+                            candidate.approached = true;
+                        }
+                    }
+                }
+
+                if new > old && new - old >= config.min_size && !open.contains_key(&key) {
+                    let Some((touch_price, touch_quantity)) = touch else {
+                        continue;
+                    };
+                    if distance_from_touch(side, price, touch_price) >= config.min_distance {
+                        open.insert(
+                            key,
+                            Candidate {
+                                side,
+                                price,
+                                size: new - old,
+                                placed_at: cur.timestamp,
+                                touch_quantity,
+                                approached: false,
+                            },
+                        );
+                    }
+                } else if new < old {
+                    let Some(candidate) = open.get(&key) else {
+                        continue;
+                    };
+                    let traded: Decimal = interval
+                        .iter()
+                        .filter(|t| t.price == price)
+                        .map(|t| t.quantity)
+                        .sum();
+                    let pulled = (old - new - traded).max(dec!(0));
+                    if traded * dec!(2) >= candidate.size {
+                        open.remove(&key);
+                    } else if pulled >= candidate.size * dec!(0.8) {
+                        let candidate = open.remove(&key).unwrap();
+                        if cur.timestamp - candidate.placed_at <= config.max_lifetime {
+                            cancelled.push(Cancelled {
+                                candidate,
+                                cancelled_at: cur.timestamp,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    let mut detections = Vec::new();
+    let mut layered = vec![false; cancelled.len()];
+
+    // Layers: same side, placed and cancelled within the window of the first
+    let mut order: Vec<usize> = (0..cancelled.len()).collect();
+    order.sort_by_key(|&i| (cancelled[i].candidate.placed_at, cancelled[i].cancelled_at));
+    for (n, &first) in order.iter().enumerate() {
+        if layered[first] {
+            continue;
+        }
+        let head = &cancelled[first];
+        let members: Vec<usize> = order[n..]
+            .iter()
+            .copied()
+            .filter(|&i| {
+                let c = &cancelled[i];
+                !layered[i]
+                    && c.candidate.side == head.candidate.side
+                    && c.candidate.placed_at - head.candidate.placed_at <= config.layering_window
+                    && (c.cancelled_at - head.cancelled_at).abs() <= config.layering_window
+            })
+            .collect();
+        if members.len() < config.layering_min_levels.max(2) {
+            continue;
+        }
+
+        let side = head.candidate.side;
+        let placed_at = head.candidate.placed_at;
+        let cancelled_at = members
+            .iter()
+            .map(|&i| cancelled[i].cancelled_at)
+            .max()
+            .unwrap();
+        let mut prices: Vec<Decimal> = members
+            .iter()
+            .map(|&i| cancelled[i].candidate.price)
+            .collect();
+        prices.sort();
+        if side == BookSide::Bid {
+            prices.reverse();
+        }
+        let size: Decimal = members.iter().map(|&i| cancelled[i].candidate.size).sum();
+        let approached = members.iter().any(|&i| cancelled[i].candidate.approached);
+        let opposite_volume = traded_volume(trades, side, placed_at, cancelled_at);
+        for &i in &members {
+            layered[i] = true;
+        }
+
+        detections.push(SpoofingDetection {
+            kind: SpoofingKind::Layering,
+            side,
+            prices,
+            size,
+            placed_at,
+            cancelled_at,
+            lifetime: cancelled_at - placed_at,
+            price_approached: approached,
+            opposite_volume,
+            confidence: score(
+                config,
+                cancelled_at - placed_at,
+                size,
+                head.candidate.touch_quantity,
+                approached,
+                opposite_volume,
+            ),
+        });
+    }
+
+    for (c, _) in cancelled.iter().zip(&layered).filter(|(_, l)| !**l) {
+        let candidate = &c.candidate;
+        let lifetime = c.cancelled_at - candidate.placed_at;
+        let opposite_volume =
+            traded_volume(trades, candidate.side, candidate.placed_at, c.cancelled_at);
+        if !candidate.approached && opposite_volume <= dec!(0) {
+            continue;
+        }
+        detections.push(SpoofingDetection {
+            kind: SpoofingKind::Spoof,
+            side: candidate.side,
+            prices: vec![candidate.price],
+            size: candidate.size,
+            placed_at: candidate.placed_at,
+            cancelled_at: c.cancelled_at,
+            lifetime,
+            price_approached: candidate.approached,
+            opposite_volume,
+            confidence: score(
+                config,
+                lifetime,
+                candidate.size,
+                candidate.touch_quantity,
+                candidate.approached,
+                opposite_volume,
+            ),
+        });
+    }
+
+    detections.sort_by_key(|d| (d.cancelled_at, d.placed_at));
+    detections
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(Decimal, Decimal)]) -> Vec<Level> {
+        levels
+            .iter()
+            .map(|&(price, quantity)| Level { price, quantity })
+            .collect()
+    }
+
+    fn book(bids: &[(Decimal, Decimal)], asks: &[(Decimal, Decimal)], timestamp: i64) -> OrderBook {
+        OrderBook {
+            bids: levels(bids),
+            asks: levels(asks),
+            timestamp,
+        }
+    }
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_spoof_cancelled_as_price_approaches() {
+        let asks = [(dec!(101), dec!(1)), (dec!(102), dec!(1))];
+        let books = vec![
+            book(&[(dec!(100), dec!(1)), (dec!(99), dec!(1))], &asks, 0),
+            // A large bid appears three ticks down; a large ask that stays
+            book(
+                &[
+                    (dec!(100), dec!(1)),
+                    (dec!(99), dec!(1)),
+                    (dec!(97), dec!(50)),
+                ],
+                &[
+                    (dec!(101), dec!(1)),
+                    (dec!(102), dec!(1)),
+                    (dec!(105), dec!(50)),
+                ],
+                1,
+            ),
+            // Sellers take out 100 and 99: the touch is one tick away
+            book(
+                &[(dec!(98), dec!(1)), (dec!(97), dec!(50))],
+                &[
+                    (dec!(101), dec!(1)),
+                    (dec!(102), dec!(1)),
+                    (dec!(105), dec!(50)),
+                ],
+                2,
+            ),
+            // Pulled without trading
+            book(
+                &[(dec!(98), dec!(1))],
+                &[
+                    (dec!(101), dec!(1)),
+                    (dec!(102), dec!(1)),
+                    (dec!(105), dec!(50)),
+                ],
+                3,
+            ),
+        ];
+        let trades = vec![
+            trade(dec!(100), dec!(1), Side::Sell, 2),
+            trade(dec!(99), dec!(1), Side::Sell, 2),
+        ];
+
+        let detections =
+            detect_spoofing_history(&books, &trades, &SpoofingConfig::new(dec!(10), dec!(1)));
+        assert_eq!(detections.len(), 1);
+        let d = &detections[0];
+        assert_eq!(d.kind, SpoofingKind::Spoof);
+        assert_eq!(d.side, BookSide::Bid);
+        assert_eq!(d.prices, vec![dec!(97)]);
+        assert_eq!(d.size, dec!(50));
+        assert_eq!((d.placed_at, d.cancelled_at, d.lifetime), (1, 3, 2));
+        assert!(d.price_approached);
+        assert_eq!(d.opposite_volume, dec!(0));
+        assert!(d.confidence > dec!(0.5) && d.confidence <= dec!(1));
+    }
+
+    #[test]
+    fn test_filled_order_is_not_spoofing() {
+        let books = vec![
+            book(&[(dec!(100), dec!(1))], &[(dec!(101), dec!(1))], 0),
+            book(
+                &[(dec!(100), dec!(1)), (dec!(97), dec!(20))],
+                &[(dec!(101), dec!(1))],
+                1,
+            ),
+            book(&[(dec!(96), dec!(1))], &[(dec!(97), dec!(1))], 2),
+        ];
+        let trades = vec![
+            trade(dec!(100), dec!(1), Side::Sell, 2),
+            trade(dec!(97), dec!(20), Side::Sell, 2),
+        ];
+        let config = SpoofingConfig::new(dec!(10), dec!(1));
+        assert!(detect_spoofing_history(&books, &trades, &config).is_empty());
+    }
+
+    #[test]
+    fn test_layering() {
+        let bids = [(dec!(100), dec!(5))];
+        let books = vec![
+            book(&bids, &[(dec!(101), dec!(5))], 0),
+            book(
+                &bids,
+                &[
+                    (dec!(101), dec!(5)),
+                    (dec!(103), dec!(20)),
+                    (dec!(104), dec!(20)),
+                    (dec!(105), dec!(20)),
+                ],
+                1,
+            ),
+            book(&[(dec!(100), dec!(2))], &[(dec!(101), dec!(5))], 2),
+        ];
+        // Sellers hit the bid while the asks are stacked
+        let trades = vec![trade(dec!(100), dec!(3), Side::Sell, 2)];
+
+        let detections =
+            detect_spoofing_history(&books, &trades, &SpoofingConfig::new(dec!(10), dec!(1)));
+        assert_eq!(detections.len(), 1);
+        let d = &detections[0];
+        assert_eq!(d.kind, SpoofingKind::Layering);
+        assert_eq!(d.side, BookSide::Ask);
+        assert_eq!(d.prices, vec![dec!(103), dec!(104), dec!(105)]);
+        assert_eq!(d.size, dec!(60));
+        assert_eq!(d.lifetime, 1);
+        assert_eq!(d.opposite_volume, dec!(3));
+    }
+}
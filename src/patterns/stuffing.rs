@@ -0,0 +1,258 @@
+//! Quote Stuffing Detection
+//!
+//! Quote stuffing floods the feed with orders that are placed and
+//! cancelled without intent to trade. Book updates are counted in fixed
+//! windows and compared with a rolling baseline of the preceding windows;
+//! a burst far above the baseline with little trading is flagged.
+
+use crate::orderbook::{BookSide, LevelUpdate};
+use crate::types::Trade;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Thresholds for [`detect_quote_stuffing`]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteStuffingConfig {
+    /// Window length in the updates' timestamp unit
+    pub window: i64,
+    /// Preceding windows averaged into the baseline
+    pub baseline_windows: usize,
+    /// Minimum ratio of messages to the baseline rate
+    pub rate_multiplier: Decimal,
+    /// Minimum messages in a window
+    pub min_messages: usize,
+    /// Maximum trades per message in a flagged window
+    pub max_trades_per_message: Decimal,
+}
+
+impl Default for QuoteStuffingConfig {
+    fn default() -> Self {
+        Self {
+            window: 1,
+            baseline_windows: 60,
+            rate_multiplier: dec!(10),
+            min_messages: 50,
+            max_trades_per_message: dec!(0.05),
+        }
+    }
+}
+
+/// A burst of book updates
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuoteStuffingEvent {
+    /// Start of the first flagged window
+    pub start: i64,
+    /// End (exclusive) of the last flagged window
+    pub end: i64,
+    pub messages: usize,
+    pub cancels: usize,
+    pub trades: usize,
+    pub trade_volume: Decimal,
+    /// Average messages per window over the baseline
+    pub baseline_messages: Decimal,
+    /// Cancels per trade, None without trades
+    pub cancel_to_trade: Option<Decimal>,
+    /// Lowest and highest updated prices
+    pub price_range: (Decimal, Decimal),
+}
+
+#[derive(Debug, Clone, Default)]
+struct WindowStats {
+    messages: usize,
+    cancels: usize,
+    trades: usize,
+    volume: Decimal,
+    traded_prices: HashSet<Decimal>,
+    low: Option<Decimal>,
+    high: Option<Decimal>,
+}
+
+/// Detect quote stuffing from book update events
+///
+/// An update that lowers the quantity at a price counts as a cancel unless
+/// a trade printed at that price in the same window. Windows are aligned to
+/// multiples of the window length. The baseline averages the preceding
+/// windows since the first update, skipping windows already flagged, and
+/// adjacent flagged windows are merged into one event.
+///
+/// # Arguments
+/// * `updates` - Level updates sorted by timestamp
+/// * `trades` - Trades over the same period
+/// * `config` - Window and thresholds
+///
+/// # Returns
+/// Vector of events, sorted by start
+#[must_use]
+pub fn detect_quote_stuffing(
+    updates: &[LevelUpdate],
+    trades: &[Trade],
+    config: &QuoteStuffingConfig,
+) -> Vec<QuoteStuffingEvent> {
+    let window = config.window.max(1);
+    let mut windows: BTreeMap<i64, WindowStats> = BTreeMap::new();
+    for trade in trades {
+        let stats = windows
+            .entry(trade.timestamp.div_euclid(window))
+            .or_default();
+        stats.trades += 1;
+        stats.volume += trade.quantity;
+        stats.traded_prices.insert(trade.price);
+    }
+
+    let mut levels: HashMap<(BookSide, Decimal), Decimal> = HashMap::new();
+    for update in updates {
+        let stats = windows
+            .entry(update.timestamp.div_euclid(window))
+            .or_default();
+        let previous = levels
+            .insert((update.side, update.price), update.quantity)
+            .unwrap_or_default();
+        stats.messages += 1;
+        if update.quantity < previous && !stats.traded_prices.contains(&update.price) {
+            stats.cancels += 1;
+        }
+        stats.low = Some(stats.low.map_or(update.price, |p| p.min(update.price)));
+        stats.high = Some(stats.high.map_or(update.price, |p| p.max(update.price)));
+    }
+
+    let Some(first) = updates.first().map(|u| u.timestamp.div_euclid(window)) else {
+        return Vec::new();
+    };
+    let mut events: Vec<QuoteStuffingEvent> = Vec::new();
+    let mut flagged: HashSet<i64> = HashSet::new();
+    for (&index, stats) in windows.range(first..) {
+        let from = index - config.baseline_windows.min((index - first) as usize) as i64;
+        let span = (from..index).filter(|i| !flagged.contains(i)).count();
+        if span == 0 || stats.messages < config.min_messages {
+            continue;
+        }
+        let messages: usize = windows
+            .range(from..index)
+            .filter(|(i, _)| !flagged.contains(i))
+            .map(|(_, s)| s.messages)
+            .sum();
+        let baseline_messages = Decimal::from(messages) / Decimal::from(span);
+        let messages = Decimal::from(stats.messages);
+        if messages < baseline_messages * config.rate_multiplier
+            || Decimal::from(stats.trades) > messages * config.max_trades_per_message
+        {
+            continue;
+        }
+        flagged.insert(index);
+
+        let start = index * window;
+        let low = stats.low.unwrap_or_default();
+        let high = stats.high.unwrap_or_default();
+        match events.last_mut() {
+            Some(event) if event.end == start => {
+                event.end = start + window;
+                event.messages += stats.messages;
+                event.cancels += stats.cancels;
+                event.trades += stats.trades;
+                event.trade_volume += stats.volume;
+                event.price_range = (event.price_range.0.min(low), event.price_range.1.max(high));
+            }
+            _ => events.push(QuoteStuffingEvent {
+                start,
+                end: start + window,
+                messages: stats.messages,
+                cancels: stats.cancels,
+                trades: stats.trades,
+                trade_volume: stats.volume,
+                baseline_messages,
+                cancel_to_trade: None,
+                price_range: (low, high),
+            }),
+        }
+    }
+
+    for event in &mut events {
+        event.cancel_to_trade =
+            (event.trades > 0).then(|| Decimal::from(event.cancels) / Decimal::from(event.trades));
+    }
+    events
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+
+    fn update(side: BookSide, price: Decimal, quantity: Decimal, timestamp: i64) -> LevelUpdate {
+        LevelUpdate {
+            update_id: 0,
+            side,
+            price,
+            quantity,
+            timestamp,
+        }
+    }
+
+    /// Five quiet updates per second, with a burst of place/cancel pairs
+    /// across 100..=104 in the burst seconds
+    fn updates(burst: &[i64]) -> Vec<LevelUpdate> {
+        let mut updates = Vec::new();
+        for second in 0..20 {
+            for i in 0..5 {
+                updates.push(update(
+                    BookSide::Bid,
+                    dec!(99),
+                    Decimal::from(i + 1),
+                    second,
+                ));
+            }
+            if burst.contains(&second) {
+                for i in 0..50 {
+                    let price = dec!(100) + Decimal::from(i % 5);
+                    updates.push(update(BookSide::Ask, price, dec!(10), second));
+                    updates.push(update(BookSide::Ask, price, dec!(0), second));
+                }
+            }
+        }
+        updates
+    }
+
+    #[test]
+    fn test_detect_quote_stuffing() {
+        let trades = vec![Trade {
+            price: dec!(99),
+            quantity: dec!(1),
+            side: Side::Sell,
+            timestamp: 10,
+        }];
+        let events = detect_quote_stuffing(
+            &updates(&[10, 11]),
+            &trades,
+            &QuoteStuffingConfig::default(),
+        );
+
+        assert_eq!(events.len(), 1);
+        let e = &events[0];
+        assert_eq!((e.start, e.end), (10, 12));
+        assert_eq!(e.messages, 210);
+        // Every burst cancel, plus the bid going from 5 back to 1 in second
+        // 11 (in second 10 a trade printed at 99)
+        assert_eq!(e.cancels, 101);
+        assert_eq!(e.trades, 1);
+        assert_eq!(e.cancel_to_trade, Some(dec!(101)));
+        assert_eq!(e.baseline_messages, dec!(5));
+        assert_eq!(e.price_range, (dec!(99), dec!(104)));
+    }
+
+    #[test]
+    fn test_burst_with_trading_is_not_stuffing() {
+        let trades: Vec<Trade> = (0..50)
+            .map(|_| Trade {
+                price: dec!(100),
+                quantity: dec!(1),
+                side: Side::Buy,
+                timestamp: 10,
+            })
+            .collect();
+        let events =
+            detect_quote_stuffing(&updates(&[10]), &trades, &QuoteStuffingConfig::default());
+        assert!(events.is_empty());
+    }
+}
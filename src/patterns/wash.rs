@@ -0,0 +1,338 @@
+//! Wash Trading and Self-Trade Detection
+//!
+//! Flags trade sequences that move no risk between owners:
+//!
+//! - wash trades: repeated buy/sell pairs of identical size and price
+//!   printed back to back
+//! - round trips: an account's trades netting to zero at an unchanged price
+//!   within a window (needs account ids)
+//! - self trades: the buyer and seller are the same account (needs account
+//!   ids)
+//!
+//! Results are [`Pattern`] variants carrying indices into the input slice.
+
+use super::Pattern;
+use crate::types::{AccountedTrade, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// Thresholds for [`detect_wash_trading`]
+///
+/// Times are in the trades' timestamp unit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WashTradingConfig {
+    /// Maximum time between the two legs of a wash pair
+    pub pair_window: i64,
+    /// Maximum gap between consecutive wash pairs, and maximum span of a
+    /// round trip
+    pub window: i64,
+    /// Maximum price difference for "identical" prices
+    pub price_tolerance: Decimal,
+    /// Minimum buy/sell pairs to flag wash trading
+    pub min_pairs: usize,
+}
+
+impl Default for WashTradingConfig {
+    /// Defaults assume second timestamps (the loader's default input unit):
+    /// legs within 1 second, pairs and round trips within 60 seconds. Scale
+    /// `pair_window` and `window` for millisecond, microsecond or nanosecond
+    /// data.
+    fn default() -> Self {
+        Self {
+            pair_window: 1,
+            window: 60,
+            price_tolerance: dec!(0),
+            min_pairs: 3,
+        }
+    }
+}
+
+/// Buy/sell pairs of identical size and price, grouped into wash patterns
+///
+/// Only adjacent trades are paired: in ordinary flow, a buy and a sell of
+/// the same size rarely print back to back, at the same price, within the
+/// pair window several times in a row.
+fn wash_trades(trades: &[&Trade], config: &WashTradingConfig) -> Vec<Pattern> {
+    let same_price = |a: &Trade, b: &Trade| (a.price - b.price).abs() <= config.price_tolerance;
+
+    let mut pairs: Vec<(usize, usize)> = Vec::new();
+    let mut index = 1;
+    while index < trades.len() {
+        let (first, second) = (trades[index - 1], trades[index]);
+        if first.side.is_known()
+            && second.side == first.side.opposite()
+            && first.quantity == second.quantity
+            && same_price(first, second)
+            && second.timestamp - first.timestamp <= config.pair_window
+        {
+            pairs.push((index - 1, index));
+            index += 2;
+        } else {
+            index += 1;
+        }
+    }
+
+    // Chain pairs of the same size and price that follow within the window
+    let mut patterns = Vec::new();
+    let mut used = vec![false; pairs.len()];
+    for first in 0..pairs.len() {
+        if used[first] {
+            continue;
+        }
+        let head = trades[pairs[first].0];
+        let mut chain = vec![first];
+        let mut last_time = trades[pairs[first].1].timestamp;
+        for next in first + 1..pairs.len() {
+            let (a, b) = pairs[next];
+            if used[next] || trades[a].quantity != head.quantity || !same_price(trades[a], head) {
+                continue;
+            }
+            if trades[a].timestamp - last_time > config.window {
+                break;
+            }
+            chain.push(next);
+            last_time = trades[b].timestamp;
+        }
+        if chain.len() < config.min_pairs.max(1) {
+            continue;
+        }
+        for &p in &chain {
+            used[p] = true;
+        }
+        patterns.push(Pattern::WashTrade {
+            price: head.price,
+            quantity: head.quantity,
+            trades: chain
+                .iter()
+                .flat_map(|&p| [pairs[p].0, pairs[p].1])
+                .collect(),
+        });
+    }
+    patterns
+}
+
+/// One account's side of a trade: (index, signed quantity)
+type Leg = (usize, Decimal);
+
+/// Split an account's legs into runs that net to zero within the window
+/// without the price moving beyond the tolerance
+///
+/// Runs are found greedily from the earliest leg and do not overlap.
+fn round_trips(trades: &[&Trade], legs: &[Leg], config: &WashTradingConfig) -> Vec<Vec<usize>> {
+    let mut runs = Vec::new();
+    let mut start = 0;
+    while start < legs.len() {
+        let first = trades[legs[start].0];
+        let mut net = dec!(0);
+        let mut end = None;
+        for (offset, &(index, signed)) in legs[start..].iter().enumerate() {
+            let trade = trades[index];
+            if trade.timestamp - first.timestamp > config.window
+                || (trade.price - first.price).abs() > config.price_tolerance
+            {
+                break;
+            }
+            net += signed;
+            if net.is_zero() {
+                end = Some(start + offset);
+                break;
+            }
+        }
+        match end {
+            Some(end) => {
+                runs.push(legs[start..=end].iter().map(|l| l.0).collect());
+                start = end + 1;
+            }
+            None => start += 1,
+        }
+    }
+    runs
+}
+
+/// Detect wash trading, round trips and self trades
+///
+/// Wash pairs need known sides. Round trips and self trades need
+/// `buyer_id`/`seller_id`: aggressor sides alone cannot attribute a
+/// position, and balanced anonymous flow is not a round trip. An account
+/// buys as buyer and sells as seller; a self trade is not part of its
+/// round trips.
+///
+/// # Arguments
+/// * `trades` - Executed trades with account ids, sorted by timestamp
+/// * `config` - Detection thresholds
+///
+/// # Returns
+/// Vector of `WashTrade`, `RoundTrip` and `SelfTrade` patterns, ordered by
+/// their first trade
+#[must_use]
+pub fn detect_wash_trading(trades: &[AccountedTrade], config: &WashTradingConfig) -> Vec<Pattern> {
+    let tape: Vec<&Trade> = trades.iter().map(|t| &t.trade).collect();
+    let mut patterns = wash_trades(&tape, config);
+
+    let mut legs: BTreeMap<&str, Vec<Leg>> = BTreeMap::new();
+    for (index, accounted) in trades.iter().enumerate() {
+        let trade = &accounted.trade;
+        match (
+            accounted.buyer_id.as_deref(),
+            accounted.seller_id.as_deref(),
+        ) {
+            (Some(buyer), Some(seller)) if buyer == seller => {
+                patterns.push(Pattern::SelfTrade {
+                    price: trade.price,
+                    quantity: trade.quantity,
+                    account: buyer.to_string(),
+                    trades: vec![index],
+                });
+            }
+            (buyer, seller) => {
+                if let Some(buyer) = buyer {
+                    legs.entry(buyer).or_default().push((index, trade.quantity));
+                }
+                if let Some(seller) = seller {
+                    legs.entry(seller)
+                        .or_default()
+                        .push((index, -trade.quantity));
+                }
+            }
+        }
+    }
+    for (account, legs) in legs {
+        for run in round_trips(&tape, &legs, config) {
+            let volume = legs
+                .iter()
+                .filter(|(i, signed)| run.contains(i) && *signed > dec!(0))
+                .map(|(_, signed)| *signed)
+                .sum();
+            patterns.push(Pattern::RoundTrip {
+                price: tape[run[0]].price,
+                volume,
+                account: account.to_string(),
+                trades: run,
+            });
+        }
+    }
+
+    patterns.sort_by_key(|p| match p {
+        Pattern::WashTrade { trades, .. }
+        | Pattern::RoundTrip { trades, .. }
+        | Pattern::SelfTrade { trades, .. } => trades.first().copied().unwrap_or_default(),
+        _ => 0,
+    });
+    patterns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+
+    fn trade(price: Decimal, quantity: Decimal, side: Side, timestamp: i64) -> AccountedTrade {
+        Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        }
+        .into()
+    }
+
+    fn with_accounts(
+        price: Decimal,
+        quantity: Decimal,
+        timestamp: i64,
+        buyer: &str,
+        seller: &str,
+    ) -> AccountedTrade {
+        AccountedTrade {
+            buyer_id: Some(buyer.to_string()),
+            seller_id: Some(seller.to_string()),
+            ..trade(price, quantity, Side::Unknown, timestamp)
+        }
+    }
+
+    #[test]
+    fn test_wash_pairs() {
+        let trades = vec![
+            trade(dec!(100), dec!(5), Side::Buy, 0),
+            trade(dec!(100), dec!(5), Side::Sell, 0),
+            trade(dec!(101), dec!(1), Side::Buy, 2),
+            trade(dec!(100), dec!(5), Side::Sell, 3),
+            trade(dec!(100), dec!(5), Side::Buy, 4),
+            trade(dec!(100), dec!(5), Side::Buy, 10),
+            trade(dec!(100), dec!(5), Side::Sell, 10),
+            // Too late to chain with the pairs above
+            trade(dec!(100), dec!(5), Side::Buy, 100),
+            trade(dec!(100), dec!(5), Side::Sell, 100),
+        ];
+        let patterns = detect_wash_trading(&trades, &WashTradingConfig::default());
+
+        assert_eq!(
+            patterns,
+            vec![Pattern::WashTrade {
+                price: dec!(100),
+                quantity: dec!(5),
+                trades: vec![0, 1, 3, 4, 5, 6],
+            }]
+        );
+
+        // Two pairs are not enough by default
+        assert!(detect_wash_trading(&trades[..5], &WashTradingConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_ordinary_flow_is_not_washing() {
+        // A liquid anonymous tape: alternating aggressors in round lots at
+        // one price, a few seconds apart, with other sizes in between
+        let sizes = [dec!(100), dec!(100), dec!(200), dec!(100), dec!(300)];
+        let trades: Vec<AccountedTrade> = (0..200)
+            .map(|i: usize| {
+                let side = [Side::Buy, Side::Sell][i % 2];
+                trade(dec!(100), sizes[i % sizes.len()], side, 2 * i as i64)
+            })
+            .collect();
+        assert!(detect_wash_trading(&trades, &WashTradingConfig::default()).is_empty());
+
+        // Bursts of round lots bouncing between bid and ask
+        let trades: Vec<AccountedTrade> = (0..200)
+            .map(|i: usize| match i % 2 {
+                0 => trade(dec!(100.01), dec!(100), Side::Buy, i as i64 / 10),
+                _ => trade(dec!(100), dec!(100), Side::Sell, i as i64 / 10),
+            })
+            .collect();
+        assert!(detect_wash_trading(&trades, &WashTradingConfig::default()).is_empty());
+    }
+
+    #[test]
+    fn test_accounts() {
+        let trades = vec![
+            with_accounts(dec!(100), dec!(2), 0, "A", "B"),
+            with_accounts(dec!(100), dec!(3), 1, "C", "D"),
+            with_accounts(dec!(100), dec!(2), 2, "C", "A"),
+            with_accounts(dec!(101), dec!(7), 3, "E", "E"),
+            // A market maker earning the spread is not a round trip
+            with_accounts(dec!(100), dec!(1), 4, "M", "F"),
+            with_accounts(dec!(100.5), dec!(1), 5, "G", "M"),
+        ];
+        let patterns = detect_wash_trading(&trades, &WashTradingConfig::default());
+
+        assert_eq!(
+            patterns,
+            vec![
+                Pattern::RoundTrip {
+                    price: dec!(100),
+                    volume: dec!(2),
+                    account: "A".to_string(),
+                    trades: vec![0, 2],
+                },
+                Pattern::SelfTrade {
+                    price: dec!(101),
+                    quantity: dec!(7),
+                    account: "E".to_string(),
+                    trades: vec![3],
+                },
+            ]
+        );
+    }
+}
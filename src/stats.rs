@@ -0,0 +1,349 @@
+//! Statistical helpers shared by the metrics
+//!
+//! These work on `f64`; callers convert from and to `Decimal` at the edges.
+
+use std::f64::consts::PI;
+
+/// Complementary error function (Numerical Recipes `erfcc`, relative
+/// error below 1.2e-7)
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1.0 / (1.0 + 0.5 * z);
+    let poly = -z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77))))))));
+    let r = t * poly.exp();
+    if x >= 0.0 {
+        r
+    } else {
+        2.0 - r
+    }
+}
+
+/// Standard normal cumulative distribution function
+pub(crate) fn normal_cdf(x: f64) -> f64 {
+    0.5 * erfc(-x / std::f64::consts::SQRT_2)
+}
+
+/// Natural log of the gamma function (Lanczos approximation)
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 6] = [
+        76.180_091_729_471_46,
+        -86.505_320_329_416_77,
+        24.014_098_240_830_91,
+        -1.231_739_572_450_155,
+        0.001_208_650_973_866_179,
+        -0.000_005_395_239_384_953,
+    ];
+    let tmp = x + 5.5;
+    let tmp = tmp - (x + 0.5) * tmp.ln();
+    let series = COEFFICIENTS
+        .iter()
+        .enumerate()
+        .fold(1.000_000_000_190_015, |acc, (i, c)| {
+            acc + c / (x + 1.0 + i as f64)
+        });
+    -tmp + (2.0 * PI).sqrt().ln() + (series / x).ln()
+}
+
+/// Continued fraction for the incomplete beta function
+fn beta_continued_fraction(a: f64, b: f64, x: f64) -> f64 {
+    const MAX_ITERATIONS: usize = 200;
+    const EPSILON: f64 = 1e-12;
+    const TINY: f64 = 1e-300;
+
+    let (qab, qap, qam) = (a + b, a + 1.0, a - 1.0);
+    let mut c = 1.0;
+    let mut d = 1.0 - qab * x / qap;
+    if d.abs() < TINY {
+        d = TINY;
+    }
+    d = 1.0 / d;
+    let mut h = d;
+
+    for m in 1..=MAX_ITERATIONS {
+        let m = m as f64;
+        let m2 = 2.0 * m;
+        for aa in [
+            m * (b - m) * x / ((qam + m2) * (a + m2)),
+            -(a + m) * (qab + m) * x / ((a + m2) * (qap + m2)),
+        ] {
+            d = 1.0 + aa * d;
+            if d.abs() < TINY {
+                d = TINY;
+            }
+            c = 1.0 + aa / c;
+            if c.abs() < TINY {
+                c = TINY;
+            }
+            d = 1.0 / d;
+            h *= d * c;
+        }
+        if (d * c - 1.0).abs() < EPSILON {
+            break;
+        }
+    }
+    h
+}
+
+/// Regularized incomplete beta function I_x(a, b)
+fn incomplete_beta(a: f64, b: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+    let front =
+        (ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln()).exp();
+    if x < (a + 1.0) / (a + b + 2.0) {
+        front * beta_continued_fraction(a, b, x) / a
+    } else {
+        1.0 - front * beta_continued_fraction(b, a, 1.0 - x) / b
+    }
+}
+
+/// Student's t cumulative distribution function
+pub(crate) fn student_t_cdf(t: f64, degrees_of_freedom: f64) -> f64 {
+    let v = degrees_of_freedom;
+    let tail = 0.5 * incomplete_beta(v / 2.0, 0.5, v / (v + t * t));
+    if t > 0.0 {
+        1.0 - tail
+    } else {
+        tail
+    }
+}
+
+/// Sample standard deviation (None with fewer than two values)
+pub(crate) fn std_dev(values: &[f64]) -> Option<f64> {
+    if values.len() < 2 {
+        return None;
+    }
+    let n = values.len() as f64;
+    let mean = values.iter().sum::<f64>() / n;
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(variance.sqrt())
+}
+
+/// Ordinary least squares fit
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct OlsFit {
+    /// Intercept followed by one slope per regressor
+    pub coefficients: Vec<f64>,
+    pub r_squared: f64,
+    pub samples: usize,
+}
+
+fn max_abs(values: impl Iterator<Item = f64>) -> f64 {
+    values.fold(0.0, |acc, v| acc.max(v.abs()))
+}
+
+/// Smallest pivot accepted by [`solve`], relative to the equilibrated system
+const PIVOT_TOLERANCE: f64 = 1e-10;
+
+/// Solve `A X = B` by Gaussian elimination with partial pivoting
+///
+/// `a` is n×n and `b` is n×m (one column per right-hand side). Rows and
+/// columns are scaled to a largest entry of one before elimination, so the
+/// singularity test does not depend on the units of the variables. Returns
+/// None when the shapes disagree or `a` is singular or ill-conditioned.
+pub(crate) fn solve(a: &[Vec<f64>], b: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let n = a.len();
+    let m = b.first().map_or(0, Vec::len);
+    if b.len() != n || a.iter().any(|row| row.len() != n) || b.iter().any(|row| row.len() != m) {
+        return None;
+    }
+
+    let mut system: Vec<Vec<f64>> = Vec::with_capacity(n);
+    for (left, right) in a.iter().zip(b) {
+        let scale = max_abs(left.iter().copied());
+        if !scale.is_normal() {
+            return None;
+        }
+        system.push(left.iter().chain(right).map(|v| v / scale).collect());
+    }
+    let mut col_scale = Vec::with_capacity(n);
+    for col in 0..n {
+        let scale = max_abs(system.iter().map(|row| row[col]));
+        if !scale.is_normal() {
+            return None;
+        }
+        for row in &mut system {
+            row[col] /= scale;
+        }
+        col_scale.push(scale);
+    }
+
+    for col in 0..n {
+        let pivot =
+            (col..n).max_by(|&p, &q| system[p][col].abs().total_cmp(&system[q][col].abs()))?;
+        if system[pivot][col].abs() < PIVOT_TOLERANCE {
+            return None;
+        }
+        system.swap(col, pivot);
+        let pivot_row = system[col].clone();
+        for (r, row) in system.iter_mut().enumerate() {
+            if r != col {
+                let factor = row[col] / pivot_row[col];
+                for (value, p) in row.iter_mut().zip(&pivot_row).skip(col) {
+                    *value -= factor * p;
+                }
+            }
+        }
+    }
+
+    Some(
+        system
+            .iter()
+            .zip(&col_scale)
+            .enumerate()
+            .map(|(i, (row, scale))| row[n..].iter().map(|v| v / row[i] / scale).collect())
+            .collect(),
+    )
+}
+
+/// Regress `y` on the rows of `x` with an intercept
+///
+/// Returns None when there are no more observations than coefficients or
+/// the regressors are collinear.
+pub(crate) fn ols(y: &[f64], x: &[Vec<f64>]) -> Option<OlsFit> {
+    let k = x.first().map_or(0, Vec::len) + 1;
+    if y.len() != x.len() || y.len() <= k || x.iter().any(|row| row.len() + 1 != k) {
+        return None;
+    }
+
+    // Normal equations X'X b = X'y with a leading column of ones
+    let mut xtx = vec![vec![0.0; k]; k];
+    let mut xty = vec![vec![0.0]; k];
+    for (row, &target) in x.iter().zip(y) {
+        let design: Vec<f64> = std::iter::once(1.0).chain(row.iter().copied()).collect();
+        for i in 0..k {
+            for j in 0..k {
+                xtx[i][j] += design[i] * design[j];
+            }
+            xty[i][0] += design[i] * target;
+        }
+    }
+    let coefficients: Vec<f64> = solve(&xtx, &xty)?.into_iter().map(|r| r[0]).collect();
+
+    let mean = y.iter().sum::<f64>() / y.len() as f64;
+    let total: f64 = y.iter().map(|v| (v - mean).powi(2)).sum();
+    let residual: f64 = x
+        .iter()
+        .zip(y)
+        .map(|(row, &target)| {
+            let fitted = coefficients[0]
+                + row
+                    .iter()
+                    .zip(&coefficients[1..])
+                    .map(|(v, c)| v * c)
+                    .sum::<f64>();
+            (target - fitted).powi(2)
+        })
+        .sum();
+    let r_squared = if total > 0.0 {
+        1.0 - residual / total
+    } else {
+        0.0
+    };
+
+    Some(OlsFit {
+        coefficients,
+        r_squared,
+        samples: y.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-6
+    }
+
+    #[test]
+    fn test_normal_cdf() {
+        assert!(close(normal_cdf(0.0), 0.5));
+        assert!(close(normal_cdf(1.96), 0.975_002_1));
+        assert!(close(normal_cdf(-1.0), 0.158_655_25));
+    }
+
+    #[test]
+    fn test_student_t_cdf() {
+        assert!(close(student_t_cdf(0.0, 5.0), 0.5));
+        // t(1) is the Cauchy distribution
+        assert!(close(student_t_cdf(1.0, 1.0), 0.75));
+        assert!(close(student_t_cdf(2.015_048, 5.0), 0.95));
+        assert!(close(student_t_cdf(-2.015_048, 5.0), 0.05));
+        // Converges to the normal for large degrees of freedom
+        assert!((student_t_cdf(1.5, 1e6) - normal_cdf(1.5)).abs() < 1e-5);
+    }
+
+    #[test]
+    fn test_ols() {
+        // y = 1 + 2 x1 - x2 exactly
+        let x = vec![
+            vec![0.0, 0.0],
+            vec![1.0, 0.0],
+            vec![0.0, 1.0],
+            vec![2.0, 3.0],
+            vec![1.0, 1.0],
+        ];
+        let y: Vec<f64> = x.iter().map(|r| 1.0 + 2.0 * r[0] - r[1]).collect();
+        let fit = ols(&y, &x).unwrap();
+
+        assert!(close(fit.coefficients[0], 1.0));
+        assert!(close(fit.coefficients[1], 2.0));
+        assert!(close(fit.coefficients[2], -1.0));
+        assert!(close(fit.r_squared, 1.0));
+        assert_eq!(fit.samples, 5);
+
+        // Collinear regressors and too few samples
+        let collinear = vec![
+            vec![1.0, 2.0],
+            vec![2.0, 4.0],
+            vec![3.0, 6.0],
+            vec![4.0, 8.0],
+        ];
+        assert!(ols(&[1.0, 2.0, 3.0, 4.0], &collinear).is_none());
+        assert!(ols(&[1.0, 2.0], &[vec![1.0], vec![2.0]]).is_none());
+    }
+
+    #[test]
+    fn test_solve() {
+        let a = vec![vec![2.0, 1.0], vec![1.0, 3.0]];
+        let b = vec![vec![3.0, 1.0], vec![5.0, 0.0]];
+        let x = solve(&a, &b).unwrap();
+
+        assert!(close(x[0][0], 0.8) && close(x[1][0], 1.4));
+        assert!(close(x[0][1], 0.6) && close(x[1][1], -0.2));
+        assert!(solve(&[vec![1.0, 2.0], vec![2.0, 4.0]], &[vec![1.0], vec![2.0]]).is_none());
+
+        // Tiny units are not mistaken for singularity
+        let tiny: Vec<Vec<f64>> = a
+            .iter()
+            .map(|r| r.iter().map(|v| v * 1e-14).collect())
+            .collect();
+        let x = solve(&tiny, &b).unwrap();
+        assert!(close(x[0][0] * 1e-14, 0.8) && close(x[1][1] * 1e-14, -0.2));
+
+        // Large units do not hide a nearly collinear system
+        let ill = vec![vec![1e6, 1e6], vec![1e6, 1e6 + 1e-5]];
+        assert!(solve(&ill, &[vec![1.0], vec![2.0]]).is_none());
+    }
+
+    #[test]
+    fn test_std_dev() {
+        assert_eq!(std_dev(&[1.0]), None);
+        assert!(close(
+            std_dev(&[2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0]).unwrap(),
+            2.138_089_9
+        ));
+    }
+}
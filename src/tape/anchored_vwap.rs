@@ -0,0 +1,307 @@
+//! Anchored VWAP with Standard-Deviation Bands
+//!
+//! An anchored VWAP accumulates trades from an anchor point: the open of
+//! each session, or a fixed timestamp such as the start of a detected
+//! absorption ([`Anchor::from_pattern`]). Bands sit 1, 2 and 3
+//! volume-weighted standard deviations of price around the VWAP.
+//! [`AnchoredVwap`] tracks several anchors at once.
+
+use crate::patterns::Pattern;
+use crate::types::Trade;
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+
+/// Where an anchored VWAP starts accumulating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Anchor {
+    /// Restart at every session open, `offset + k * length`, in the trades'
+    /// timestamp unit (e.g. `length = 86400` for daily sessions)
+    Session { length: i64, offset: i64 },
+    /// Start at a timestamp and never reset
+    At(i64),
+}
+
+impl Anchor {
+    /// Anchor at a detected event
+    ///
+    /// # Returns
+    /// `Anchor::At` the event's timestamp, or None for patterns that carry
+    /// no time (only `Pattern::Absorption` does)
+    #[must_use]
+    pub fn from_pattern(pattern: &Pattern) -> Option<Anchor> {
+        match *pattern {
+            Pattern::Absorption { timestamp, .. } => Some(Anchor::At(timestamp)),
+            _ => None,
+        }
+    }
+
+    /// Start of the accumulation period containing `timestamp`, or None if
+    /// the anchor has not started yet
+    fn period_start(self, timestamp: i64) -> Option<i64> {
+        match self {
+            Anchor::Session { length, offset } => {
+                let length = length.max(1);
+                Some((timestamp - offset).div_euclid(length) * length + offset)
+            }
+            Anchor::At(start) => (timestamp >= start).then_some(start),
+        }
+    }
+}
+
+/// VWAP and its bands at one point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct VwapBands {
+    /// Timestamp of the last trade included
+    pub timestamp: i64,
+    /// Start of the current accumulation period
+    pub anchor_start: i64,
+    pub vwap: Decimal,
+    /// Volume-weighted standard deviation of price
+    pub std_dev: Decimal,
+    /// VWAP + 1, 2 and 3 standard deviations
+    pub upper: [Decimal; 3],
+    /// VWAP - 1, 2 and 3 standard deviations
+    pub lower: [Decimal; 3],
+    pub volume: Decimal,
+}
+
+#[derive(Debug, Clone)]
+struct AnchorState {
+    label: String,
+    anchor: Anchor,
+    start: Option<i64>,
+    timestamp: i64,
+    value: Decimal,
+    squares: Decimal,
+    volume: Decimal,
+}
+
+impl AnchorState {
+    fn update(&mut self, trade: &Trade) {
+        let Some(start) = self.anchor.period_start(trade.timestamp) else {
+            return;
+        };
+        if self.start != Some(start) {
+            self.start = Some(start);
+            self.value = dec!(0);
+            self.squares = dec!(0);
+            self.volume = dec!(0);
+        }
+        self.timestamp = trade.timestamp;
+        self.value += trade.price * trade.quantity;
+        self.squares += trade.price * trade.price * trade.quantity;
+        self.volume += trade.quantity;
+    }
+
+    fn bands(&self) -> Option<VwapBands> {
+        let anchor_start = self.start?;
+        if self.volume <= dec!(0) {
+            return None;
+        }
+        let vwap = self.value / self.volume;
+        let variance = (self.squares / self.volume - vwap * vwap).max(dec!(0));
+        let std_dev = variance
+            .to_f64()
+            .and_then(|v| Decimal::from_f64(v.sqrt()))
+            .unwrap_or_default();
+        let band = |k: Decimal| (vwap + std_dev * k, vwap - std_dev * k);
+        let (u1, l1) = band(dec!(1));
+        let (u2, l2) = band(dec!(2));
+        let (u3, l3) = band(dec!(3));
+
+        Some(VwapBands {
+            timestamp: self.timestamp,
+            anchor_start,
+            vwap,
+            std_dev,
+            upper: [u1, u2, u3],
+            lower: [l1, l2, l3],
+            volume: self.volume,
+        })
+    }
+}
+
+/// Several anchored VWAPs updated from one trade stream
+#[derive(Debug, Clone, Default)]
+pub struct AnchoredVwap {
+    anchors: Vec<AnchorState>,
+}
+
+impl AnchoredVwap {
+    /// Create a calculator without anchors
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an anchor, replacing any anchor with the same label
+    ///
+    /// Only trades fed after this call are included.
+    pub fn add_anchor(&mut self, label: impl Into<String>, anchor: Anchor) {
+        let label = label.into();
+        self.remove_anchor(&label);
+        self.anchors.push(AnchorState {
+            label,
+            anchor,
+            start: None,
+            timestamp: 0,
+            value: dec!(0),
+            squares: dec!(0),
+            volume: dec!(0),
+        });
+    }
+
+    /// Remove an anchor, returning whether it existed
+    pub fn remove_anchor(&mut self, label: &str) -> bool {
+        let before = self.anchors.len();
+        self.anchors.retain(|a| a.label != label);
+        self.anchors.len() != before
+    }
+
+    /// Anchor labels in the order they were added
+    pub fn labels(&self) -> impl Iterator<Item = &str> {
+        self.anchors.iter().map(|a| a.label.as_str())
+    }
+
+    /// Feed one trade to every anchor
+    ///
+    /// # Returns
+    /// Bands of each anchor that has started, in the order anchors were added
+    pub fn update(&mut self, trade: &Trade) -> Vec<(&str, VwapBands)> {
+        for anchor in &mut self.anchors {
+            anchor.update(trade);
+        }
+        self.anchors
+            .iter()
+            .filter_map(|a| Some((a.label.as_str(), a.bands()?)))
+            .collect()
+    }
+
+    /// Current bands of one anchor
+    #[must_use]
+    pub fn bands(&self, label: &str) -> Option<VwapBands> {
+        self.anchors.iter().find(|a| a.label == label)?.bands()
+    }
+}
+
+/// Calculate the anchored VWAP band series over a trade slice
+///
+/// # Arguments
+/// * `trades` - Executed trades, sorted by timestamp
+/// * `anchor` - Session or timestamp anchor
+///
+/// # Returns
+/// One VwapBands per trade from the anchor on
+#[must_use]
+pub fn anchored_vwap(trades: &[Trade], anchor: Anchor) -> Vec<VwapBands> {
+    let mut calculator = AnchoredVwap::new();
+    calculator.add_anchor("", anchor);
+    trades
+        .iter()
+        .filter_map(|t| calculator.update(t).pop().map(|(_, bands)| bands))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::patterns::detect_absorption;
+    use crate::tape::calculate_vwap;
+    use crate::types::Side;
+
+    fn trade(price: Decimal, quantity: Decimal, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity,
+            side: Side::Unknown,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_bands() {
+        let trades = vec![trade(dec!(100), dec!(1), 0), trade(dec!(102), dec!(1), 1)];
+        let series = anchored_vwap(&trades, Anchor::At(0));
+
+        let last = series.last().unwrap();
+        assert_eq!(last.vwap, dec!(101));
+        assert_eq!(last.std_dev, dec!(1));
+        assert_eq!(last.upper, [dec!(102), dec!(103), dec!(104)]);
+        assert_eq!(last.lower, [dec!(100), dec!(99), dec!(98)]);
+        assert_eq!(Some(last.vwap), calculate_vwap(&trades));
+        assert_eq!(series[0].std_dev, dec!(0));
+    }
+
+    #[test]
+    fn test_session_reset() {
+        let trades = vec![
+            trade(dec!(100), dec!(1), 10),
+            trade(dec!(110), dec!(1), 90),
+            trade(dec!(120), dec!(2), 110),
+        ];
+        let series = anchored_vwap(
+            &trades,
+            Anchor::Session {
+                length: 100,
+                offset: 0,
+            },
+        );
+
+        assert_eq!(series[1].vwap, dec!(105));
+        assert_eq!(series[2].anchor_start, 100);
+        assert_eq!(series[2].vwap, dec!(120));
+        assert_eq!(series[2].volume, dec!(2));
+    }
+
+    #[test]
+    fn test_concurrent_anchors() {
+        let mut calculator = AnchoredVwap::new();
+        calculator.add_anchor(
+            "session",
+            Anchor::Session {
+                length: 1000,
+                offset: 0,
+            },
+        );
+        calculator.add_anchor("absorption", Anchor::At(2));
+
+        let first = calculator.update(&trade(dec!(100), dec!(1), 1));
+        assert_eq!(first.len(), 1);
+        assert_eq!(first[0].0, "session");
+
+        calculator.update(&trade(dec!(104), dec!(1), 2));
+        assert_eq!(calculator.bands("session").unwrap().vwap, dec!(102));
+        assert_eq!(calculator.bands("absorption").unwrap().vwap, dec!(104));
+
+        assert!(calculator.remove_anchor("absorption"));
+        assert_eq!(calculator.labels().collect::<Vec<_>>(), vec!["session"]);
+        assert!(calculator.bands("absorption").is_none());
+    }
+
+    #[test]
+    fn test_anchor_to_detected_absorption() {
+        let trades = vec![
+            trade(dec!(90), dec!(1), 0),
+            trade(dec!(100), dec!(10), 5),
+            trade(dec!(100.5), dec!(10), 6),
+            trade(dec!(110), dec!(1), 9),
+        ];
+        let absorption = detect_absorption(&trades[1..3], dec!(15), dec!(1));
+        let anchor = Anchor::from_pattern(&absorption[0]).unwrap();
+        assert_eq!(anchor, Anchor::At(5));
+
+        let series = anchored_vwap(&trades, anchor);
+        assert_eq!(series.len(), 3);
+        assert_eq!(series[0].anchor_start, 5);
+        assert_eq!(series[1].vwap, dec!(100.25));
+        assert_eq!(series[2].volume, dec!(21));
+
+        let support = Pattern::Support {
+            price: dec!(100),
+            strength: dec!(1),
+        };
+        assert_eq!(Anchor::from_pattern(&support), None);
+    }
+}
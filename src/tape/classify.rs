@@ -0,0 +1,397 @@
+//! Trade Side Classification
+//!
+//! Infers the aggressor side of trades that arrive without one, from trade
+//! prices alone (tick tests) or from the prevailing quotes (quote rule,
+//! Lee-Ready, EMO and CLNV). Quotes are `OrderBook` snapshots sorted by
+//! timestamp; the prevailing quote for a trade is the latest snapshot at or
+//! before `trade.timestamp - quote_lag`.
+
+use crate::orderbook::{best_ask, best_bid, mid_price, prevailing_book};
+use crate::types::{OrderBook, Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Side classification algorithm
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ClassificationMethod {
+    /// Compare with the last different price
+    TickTest,
+    /// Compare with the next different price
+    ReverseTickTest,
+    /// Compare with the prevailing mid price
+    QuoteRule,
+    /// Quote rule, falling back to the tick test at the mid
+    #[default]
+    LeeReady,
+    /// Ellis-Michaely-O'Hara: trades at the ask are buys, at the bid sells,
+    /// anything else goes to the tick test
+    Emo,
+    /// Chakrabarty-Li-Nguyen-Van Ness: trades in the upper 30% of the spread
+    /// are buys, in the lower 30% sells, anything else goes to the tick test
+    Clnv,
+}
+
+impl ClassificationMethod {
+    /// Whether the method needs quotes
+    #[must_use]
+    pub fn uses_quotes(self) -> bool {
+        !matches!(self, Self::TickTest | Self::ReverseTickTest)
+    }
+}
+
+impl fmt::Display for ClassificationMethod {
+    /// The CLI spelling, accepted back by [`FromStr`]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::TickTest => "tick",
+            Self::ReverseTickTest => "reverse-tick",
+            Self::QuoteRule => "quote",
+            Self::LeeReady => "lee-ready",
+            Self::Emo => "emo",
+            Self::Clnv => "clnv",
+        })
+    }
+}
+
+impl FromStr for ClassificationMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "tick" | "tick-test" => Ok(Self::TickTest),
+            "reverse-tick" | "reverse-tick-test" => Ok(Self::ReverseTickTest),
+            "quote" | "quote-rule" => Ok(Self::QuoteRule),
+            "lee-ready" | "lr" => Ok(Self::LeeReady),
+            "emo" => Ok(Self::Emo),
+            "clnv" => Ok(Self::Clnv),
+            other => Err(format!("unknown classification method '{other}'")),
+        }
+    }
+}
+
+/// Classifier settings
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClassifierConfig {
+    pub method: ClassificationMethod,
+    /// Delay subtracted from trade timestamps before looking up quotes, in
+    /// the trades' timestamp unit (Lee and Ready used 5 seconds)
+    pub quote_lag: i64,
+}
+
+/// Classify trades with the tick test
+///
+/// Upticks and zero-upticks are buys, downticks and zero-downticks are
+/// sells. Trades before the first price change are `Unknown`.
+#[must_use]
+pub fn tick_test(trades: &[Trade]) -> Vec<Side> {
+    let mut last = Side::Unknown;
+    let mut sides = Vec::with_capacity(trades.len());
+
+    for (i, trade) in trades.iter().enumerate() {
+        if let Some(previous) = i.checked_sub(1).map(|j| trades[j].price) {
+            if trade.price > previous {
+                last = Side::Buy;
+            } else if trade.price < previous {
+                last = Side::Sell;
+            }
+        }
+        sides.push(last);
+    }
+
+    sides
+}
+
+/// Classify trades with the reverse tick test
+///
+/// A trade followed by a lower price is a buy, followed by a higher price a
+/// sell. Trades after the last price change are `Unknown`.
+#[must_use]
+pub fn reverse_tick_test(trades: &[Trade]) -> Vec<Side> {
+    let mut next = Side::Unknown;
+    let mut sides = vec![Side::Unknown; trades.len()];
+
+    for i in (0..trades.len()).rev() {
+        if let Some(following) = trades.get(i + 1).map(|t| t.price) {
+            if trades[i].price > following {
+                next = Side::Buy;
+            } else if trades[i].price < following {
+                next = Side::Sell;
+            }
+        }
+        sides[i] = next;
+    }
+
+    sides
+}
+
+/// Prevailing quote for each trade
+fn quotes<'a>(
+    trades: &'a [Trade],
+    books: &'a [OrderBook],
+    quote_lag: i64,
+) -> impl Iterator<Item = Option<&'a OrderBook>> + 'a {
+    trades
+        .iter()
+        .map(move |t| prevailing_book(books, t.timestamp - quote_lag))
+}
+
+/// Side from a price's position relative to the mid
+fn versus_mid(price: Decimal, book: Option<&OrderBook>) -> Side {
+    match book.and_then(mid_price) {
+        Some(mid) if price > mid => Side::Buy,
+        Some(mid) if price < mid => Side::Sell,
+        _ => Side::Unknown,
+    }
+}
+
+/// Classify trades with the quote rule
+///
+/// Trades above the prevailing mid are buys, below it sells. Trades at the
+/// mid or without a two-sided quote are `Unknown`.
+#[must_use]
+pub fn quote_rule(trades: &[Trade], books: &[OrderBook], quote_lag: i64) -> Vec<Side> {
+    trades
+        .iter()
+        .zip(quotes(trades, books, quote_lag))
+        .map(|(t, book)| versus_mid(t.price, book))
+        .collect()
+}
+
+/// Use `classify` where it decides, the tick test elsewhere
+fn with_tick_fallback<F>(
+    trades: &[Trade],
+    books: &[OrderBook],
+    quote_lag: i64,
+    classify: F,
+) -> Vec<Side>
+where
+    F: Fn(Decimal, Option<&OrderBook>) -> Side,
+{
+    trades
+        .iter()
+        .zip(quotes(trades, books, quote_lag))
+        .zip(tick_test(trades))
+        .map(|((t, book), tick)| match classify(t.price, book) {
+            Side::Unknown => tick,
+            side => side,
+        })
+        .collect()
+}
+
+/// Classify trades with the Lee-Ready algorithm
+///
+/// The quote rule, with the tick test for trades at the mid or without a
+/// quote.
+#[must_use]
+pub fn lee_ready(trades: &[Trade], books: &[OrderBook], quote_lag: i64) -> Vec<Side> {
+    with_tick_fallback(trades, books, quote_lag, versus_mid)
+}
+
+/// Classify trades with the Ellis-Michaely-O'Hara rule
+#[must_use]
+pub fn emo(trades: &[Trade], books: &[OrderBook], quote_lag: i64) -> Vec<Side> {
+    with_tick_fallback(trades, books, quote_lag, |price, book| {
+        let Some(book) = book else {
+            return Side::Unknown;
+        };
+        if best_ask(book) == Some(price) {
+            Side::Buy
+        } else if best_bid(book) == Some(price) {
+            Side::Sell
+        } else {
+            Side::Unknown
+        }
+    })
+}
+
+/// Classify trades with the Chakrabarty-Li-Nguyen-Van Ness rule
+#[must_use]
+pub fn clnv(trades: &[Trade], books: &[OrderBook], quote_lag: i64) -> Vec<Side> {
+    with_tick_fallback(trades, books, quote_lag, |price, book| {
+        let Some((bid, ask)) = book.and_then(|b| best_bid(b).zip(best_ask(b))) else {
+            return Side::Unknown;
+        };
+        let band = (ask - bid) * dec!(0.3);
+        if price <= ask && price >= ask - band {
+            Side::Buy
+        } else if price >= bid && price <= bid + band {
+            Side::Sell
+        } else {
+            Side::Unknown
+        }
+    })
+}
+
+/// Classify every trade with the configured method
+///
+/// Methods that do not use quotes ignore `books`.
+#[must_use]
+pub fn classify_sides(
+    trades: &[Trade],
+    books: &[OrderBook],
+    config: &ClassifierConfig,
+) -> Vec<Side> {
+    let lag = config.quote_lag;
+    match config.method {
+        ClassificationMethod::TickTest => tick_test(trades),
+        ClassificationMethod::ReverseTickTest => reverse_tick_test(trades),
+        ClassificationMethod::QuoteRule => quote_rule(trades, books, lag),
+        ClassificationMethod::LeeReady => lee_ready(trades, books, lag),
+        ClassificationMethod::Emo => emo(trades, books, lag),
+        ClassificationMethod::Clnv => clnv(trades, books, lag),
+    }
+}
+
+/// Fill in the side of trades without an aggressor flag
+///
+/// Trades that already have a known side keep it, so the result can be fed
+/// to [`crate::metrics::calculate_delta`] and friends.
+#[must_use]
+pub fn infer_sides(trades: &[Trade], books: &[OrderBook], config: &ClassifierConfig) -> Vec<Trade> {
+    trades
+        .iter()
+        .zip(classify_sides(trades, books, config))
+        .map(|(trade, inferred)| Trade {
+            side: if trade.side.is_known() {
+                trade.side
+            } else {
+                inferred
+            },
+            ..trade.clone()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::calculate_delta;
+    use crate::types::Level;
+
+    fn trade(price: Decimal, timestamp: i64) -> Trade {
+        Trade {
+            price,
+            quantity: dec!(1),
+            side: Side::Unknown,
+            timestamp,
+        }
+    }
+
+    fn book(bid: Decimal, ask: Decimal, timestamp: i64) -> OrderBook {
+        OrderBook {
+            bids: vec![Level {
+                price: bid,
+                quantity: dec!(1),
+            }],
+            asks: vec![Level {
+                price: ask,
+                quantity: dec!(1),
+            }],
+            timestamp,
+        }
+    }
+
+    fn prices(prices: &[Decimal]) -> Vec<Trade> {
+        prices
+            .iter()
+            .enumerate()
+            .map(|(i, &p)| trade(p, i as i64))
+            .collect()
+    }
+
+    #[test]
+    fn test_tick_tests() {
+        let trades = prices(&[dec!(100), dec!(101), dec!(101), dec!(100), dec!(100)]);
+
+        assert_eq!(
+            tick_test(&trades),
+            vec![Side::Unknown, Side::Buy, Side::Buy, Side::Sell, Side::Sell]
+        );
+        assert_eq!(
+            reverse_tick_test(&trades),
+            vec![
+                Side::Sell,
+                Side::Buy,
+                Side::Buy,
+                Side::Unknown,
+                Side::Unknown
+            ]
+        );
+    }
+
+    #[test]
+    fn test_quote_rule_and_lee_ready() {
+        let books = vec![book(dec!(99), dec!(101), 0), book(dec!(100), dec!(102), 10)];
+        let trades = vec![
+            trade(dec!(101), 5),
+            trade(dec!(100), 6),
+            trade(dec!(101), 12),
+        ];
+
+        // The last trade is at the new mid of 101
+        assert_eq!(
+            quote_rule(&trades, &books, 0),
+            vec![Side::Buy, Side::Unknown, Side::Unknown]
+        );
+        // Lagged by 5 it is still priced against the old quote
+        assert_eq!(quote_rule(&trades, &books, 5)[2], Side::Buy);
+        assert_eq!(
+            lee_ready(&trades, &books, 0),
+            vec![Side::Buy, Side::Sell, Side::Buy]
+        );
+    }
+
+    #[test]
+    fn test_emo_and_clnv() {
+        let books = vec![book(dec!(100), dec!(110), 0)];
+        let trades = vec![
+            trade(dec!(105), 1),
+            trade(dec!(110), 2),
+            trade(dec!(108), 3),
+            trade(dec!(102), 4),
+            trade(dec!(100), 5),
+        ];
+
+        // 108 is a downtick for EMO but inside the ask band for CLNV
+        assert_eq!(
+            emo(&trades, &books, 0),
+            vec![Side::Unknown, Side::Buy, Side::Sell, Side::Sell, Side::Sell]
+        );
+        assert_eq!(
+            clnv(&trades, &books, 0),
+            vec![Side::Unknown, Side::Buy, Side::Buy, Side::Sell, Side::Sell]
+        );
+    }
+
+    #[test]
+    fn test_infer_sides_keeps_known() {
+        let mut trades = prices(&[dec!(100), dec!(101), dec!(102), dec!(101)]);
+        trades[3].side = Side::Buy;
+        let config = ClassifierConfig {
+            method: ClassificationMethod::TickTest,
+            quote_lag: 0,
+        };
+
+        let signed = infer_sides(&trades, &[], &config);
+        let sides: Vec<Side> = signed.iter().map(|t| t.side).collect();
+
+        assert_eq!(sides, vec![Side::Unknown, Side::Buy, Side::Buy, Side::Buy]);
+        assert_eq!(calculate_delta(&signed), dec!(3));
+    }
+
+    #[test]
+    fn test_method_from_str() {
+        assert_eq!("Lee_Ready".parse(), Ok(ClassificationMethod::LeeReady));
+        assert_eq!("tick".parse(), Ok(ClassificationMethod::TickTest));
+        assert!("midpoint".parse::<ClassificationMethod>().is_err());
+        assert!(!ClassificationMethod::ReverseTickTest.uses_quotes());
+        assert_eq!(ClassificationMethod::LeeReady.to_string(), "lee-ready");
+        assert_eq!(
+            "reverse-tick".parse(),
+            Ok(ClassificationMethod::ReverseTickTest)
+        );
+    }
+}
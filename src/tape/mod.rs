@@ -1,8 +1,27 @@
 //! Tape Reading Module
 //!
 //! This module provides functionality for analyzing trade flow (time & sales).
-
-use crate::types::Trade;
+//! Trades without an aggressor flag can be signed with the classifiers in
+//! [`infer_sides`] (tick test, quote rule, Lee-Ready, EMO, CLNV). Live
+//! feeds can use the incremental [`TradeAccumulator`]s instead of rescanning
+//! slices. [`AnchoredVwap`] tracks session and event-anchored VWAPs with
+//! standard-deviation bands.
+
+mod anchored_vwap;
+mod classify;
+mod streaming;
+
+pub use anchored_vwap::{anchored_vwap, Anchor, AnchoredVwap, VwapBands};
+pub use classify::{
+    classify_sides, clnv, emo, infer_sides, lee_ready, quote_rule, reverse_tick_test, tick_test,
+    ClassificationMethod, ClassifierConfig,
+};
+pub use streaming::{
+    Rolling, RollingWindow, StreamingAggressionRatio, StreamingCvd, StreamingDelta,
+    StreamingTradePressure, StreamingVwap, TradeAccumulator,
+};
+
+use crate::types::{Side, Trade};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
@@ -13,21 +32,23 @@ pub enum TradeType {
     Buy,
     /// Aggressive sell
     Sell,
+    /// Trade without aggressor information
+    Unknown,
     /// Block trade (large trade)
-    Block { side: String },
+    Block { side: Side },
 }
 
 /// Analyze a single trade
 #[must_use]
 pub fn classify_trade(trade: &Trade, block_threshold: Decimal) -> TradeType {
     if trade.quantity >= block_threshold {
-        TradeType::Block {
-            side: trade.side.clone(),
-        }
-    } else if trade.side == "buy" {
-        TradeType::Buy
-    } else {
-        TradeType::Sell
+        return TradeType::Block { side: trade.side };
+    }
+
+    match trade.side {
+        Side::Buy => TradeType::Buy,
+        Side::Sell => TradeType::Sell,
+        Side::Unknown => TradeType::Unknown,
     }
 }
 
@@ -37,18 +58,19 @@ pub fn classify_trade(trade: &Trade, block_threshold: Decimal) -> TradeType {
 /// * `trades` - List of trades
 ///
 /// # Returns
-/// (buy_volume, sell_volume, net_volume)
+/// (buy_volume, sell_volume, net_volume). Trades with an unknown aggressor
+/// are counted on neither side.
 #[must_use]
 pub fn calculate_trade_pressure(trades: &[Trade]) -> (Decimal, Decimal, Decimal) {
     let buy_volume: Decimal = trades
         .iter()
-        .filter(|t| t.side == "buy")
+        .filter(|t| t.side == Side::Buy)
         .map(|t| t.quantity)
         .sum();
 
     let sell_volume: Decimal = trades
         .iter()
-        .filter(|t| t.side == "sell")
+        .filter(|t| t.side == Side::Sell)
         .map(|t| t.quantity)
         .sum();
 
@@ -76,23 +98,23 @@ pub fn identify_block_trades(trades: &[Trade], threshold: Decimal) -> Vec<Trade>
 
 /// Calculate trade aggression ratio
 ///
-/// Ratio of aggressive buys to total trades
+/// Ratio of aggressive buys to trades with a known aggressor
 /// High ratio (>0.6) indicates strong buying aggression
 ///
 /// # Arguments
 /// * `trades` - List of trades
 ///
 /// # Returns
-/// Aggression ratio between 0.0 and 1.0
+/// Aggression ratio between 0.0 and 1.0 (0.5 when no trade has a known side)
 #[must_use]
 pub fn calculate_aggression_ratio(trades: &[Trade]) -> Decimal {
-    if trades.is_empty() {
+    let buy_count = trades.iter().filter(|t| t.side == Side::Buy).count();
+    let total_count = trades.iter().filter(|t| t.side.is_known()).count();
+
+    if total_count == 0 {
         return dec!(0.5);
     }
 
-    let buy_count = trades.iter().filter(|t| t.side == "buy").count();
-    let total_count = trades.len();
-
     Decimal::from(buy_count) / Decimal::from(total_count)
 }
 
@@ -170,25 +192,25 @@ mod tests {
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1000,
             },
             Trade {
                 price: dec!(50001.0),
                 quantity: dec!(0.5),
-                side: "sell".to_string(),
+                side: Side::Sell,
                 timestamp: 1001,
             },
             Trade {
                 price: dec!(50002.0),
                 quantity: dec!(2.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1002,
             },
             Trade {
                 price: dec!(50003.0),
                 quantity: dec!(0.3),
-                side: "sell".to_string(),
+                side: Side::Sell,
                 timestamp: 1003,
             },
         ]
@@ -199,22 +221,20 @@ mod tests {
         let trade1 = Trade {
             price: dec!(50000.0),
             quantity: dec!(1.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1000,
         };
         let trade2 = Trade {
             price: dec!(50000.0),
             quantity: dec!(10.0),
-            side: "buy".to_string(),
+            side: Side::Buy,
             timestamp: 1000,
         };
 
         assert_eq!(classify_trade(&trade1, dec!(5.0)), TradeType::Buy);
         assert_eq!(
             classify_trade(&trade2, dec!(5.0)),
-            TradeType::Block {
-                side: "buy".to_string()
-            }
+            TradeType::Block { side: Side::Buy }
         );
     }
 
@@ -252,31 +272,31 @@ mod tests {
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1000,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1001,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1002,
             },
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1010,
             }, // Gap
             Trade {
                 price: dec!(50000.0),
                 quantity: dec!(1.0),
-                side: "buy".to_string(),
+                side: Side::Buy,
                 timestamp: 1011,
             },
         ];
@@ -0,0 +1,421 @@
+//! Streaming Tape Metrics
+//!
+//! Incremental versions of the batch tape metrics. Each accumulator is fed
+//! one trade at a time with [`TradeAccumulator::update`] and reports the
+//! same value as the batch function over every trade seen so far.
+//! [`Rolling`] wraps any accumulator to cover only the most recent trades,
+//! by time or by count.
+
+use crate::types::{Side, Trade};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use std::collections::VecDeque;
+
+/// A metric maintained incrementally over a trade stream
+pub trait TradeAccumulator {
+    type Output;
+
+    /// Add a trade
+    fn update(&mut self, trade: &Trade);
+
+    /// Remove a trade previously added (used by [`Rolling`])
+    fn remove(&mut self, trade: &Trade);
+
+    /// Current value
+    fn value(&self) -> Self::Output;
+}
+
+/// Streaming [`super::calculate_vwap`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingVwap {
+    value: Decimal,
+    volume: Decimal,
+    trades: usize,
+}
+
+impl TradeAccumulator for StreamingVwap {
+    type Output = Option<Decimal>;
+
+    fn update(&mut self, trade: &Trade) {
+        self.value += trade.price * trade.quantity;
+        self.volume += trade.quantity;
+        self.trades += 1;
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        self.value -= trade.price * trade.quantity;
+        self.volume -= trade.quantity;
+        self.trades = self.trades.saturating_sub(1);
+    }
+
+    fn value(&self) -> Option<Decimal> {
+        if self.trades == 0 || self.volume == dec!(0) {
+            return None;
+        }
+        Some(self.value / self.volume)
+    }
+}
+
+/// Streaming [`crate::metrics::calculate_delta`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingDelta {
+    delta: Decimal,
+}
+
+impl TradeAccumulator for StreamingDelta {
+    type Output = Decimal;
+
+    fn update(&mut self, trade: &Trade) {
+        self.delta += trade.signed_quantity();
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        self.delta -= trade.signed_quantity();
+    }
+
+    fn value(&self) -> Decimal {
+        self.delta
+    }
+}
+
+/// Streaming [`crate::metrics::calculate_cvd`]
+///
+/// The value is the last point of the batch series: the latest timestamp
+/// and the cumulative delta, or None before the first trade.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingCvd {
+    delta: StreamingDelta,
+    timestamp: Option<i64>,
+}
+
+impl TradeAccumulator for StreamingCvd {
+    type Output = Option<(i64, Decimal)>;
+
+    fn update(&mut self, trade: &Trade) {
+        self.delta.update(trade);
+        self.timestamp = Some(trade.timestamp);
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        self.delta.remove(trade);
+    }
+
+    fn value(&self) -> Option<(i64, Decimal)> {
+        self.timestamp.map(|ts| (ts, self.delta.value()))
+    }
+}
+
+/// Streaming [`super::calculate_trade_pressure`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingTradePressure {
+    buy_volume: Decimal,
+    sell_volume: Decimal,
+}
+
+impl TradeAccumulator for StreamingTradePressure {
+    /// (buy_volume, sell_volume, net_volume)
+    type Output = (Decimal, Decimal, Decimal);
+
+    fn update(&mut self, trade: &Trade) {
+        match trade.side {
+            Side::Buy => self.buy_volume += trade.quantity,
+            Side::Sell => self.sell_volume += trade.quantity,
+            Side::Unknown => {}
+        }
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        match trade.side {
+            Side::Buy => self.buy_volume -= trade.quantity,
+            Side::Sell => self.sell_volume -= trade.quantity,
+            Side::Unknown => {}
+        }
+    }
+
+    fn value(&self) -> (Decimal, Decimal, Decimal) {
+        (
+            self.buy_volume,
+            self.sell_volume,
+            self.buy_volume - self.sell_volume,
+        )
+    }
+}
+
+/// Streaming [`super::calculate_aggression_ratio`]
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StreamingAggressionRatio {
+    buys: usize,
+    known: usize,
+}
+
+impl TradeAccumulator for StreamingAggressionRatio {
+    type Output = Decimal;
+
+    fn update(&mut self, trade: &Trade) {
+        if trade.side == Side::Buy {
+            self.buys += 1;
+        }
+        if trade.side.is_known() {
+            self.known += 1;
+        }
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        if trade.side == Side::Buy {
+            self.buys = self.buys.saturating_sub(1);
+        }
+        if trade.side.is_known() {
+            self.known = self.known.saturating_sub(1);
+        }
+    }
+
+    fn value(&self) -> Decimal {
+        if self.known == 0 {
+            return dec!(0.5);
+        }
+        Decimal::from(self.buys) / Decimal::from(self.known)
+    }
+}
+
+/// Which trades a [`Rolling`] accumulator keeps
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RollingWindow {
+    /// Trades with `timestamp > newest - duration`, in the trades'
+    /// timestamp unit, where `newest` is the latest timestamp seen
+    Time(i64),
+    /// The most recent N trades
+    Count(usize),
+}
+
+/// An accumulator over a rolling window of trades
+///
+/// Its value equals the batch function over the trades still in the
+/// window.
+#[derive(Debug, Clone)]
+pub struct Rolling<A> {
+    inner: A,
+    window: RollingWindow,
+    trades: VecDeque<Trade>,
+    newest: Option<i64>,
+}
+
+impl<A: TradeAccumulator + Default> Rolling<A> {
+    /// Keep trades from the last `duration` time units
+    #[must_use]
+    pub fn by_time(duration: i64) -> Self {
+        Self::new(RollingWindow::Time(duration))
+    }
+
+    /// Keep the last `count` trades
+    #[must_use]
+    pub fn by_count(count: usize) -> Self {
+        Self::new(RollingWindow::Count(count))
+    }
+
+    /// Create an empty rolling accumulator
+    ///
+    /// Windows always hold at least the newest trade.
+    #[must_use]
+    pub fn new(window: RollingWindow) -> Self {
+        let window = match window {
+            RollingWindow::Time(d) => RollingWindow::Time(d.max(1)),
+            RollingWindow::Count(n) => RollingWindow::Count(n.max(1)),
+        };
+        Self {
+            inner: A::default(),
+            window,
+            trades: VecDeque::new(),
+            newest: None,
+        }
+    }
+}
+
+impl<A> Rolling<A> {
+    /// Trades currently in the window, oldest first
+    pub fn trades(&self) -> impl Iterator<Item = &Trade> {
+        self.trades.iter()
+    }
+
+    /// Number of trades in the window
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.trades.len()
+    }
+
+    /// Whether the window holds no trades
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.trades.is_empty()
+    }
+}
+
+impl<A: TradeAccumulator> TradeAccumulator for Rolling<A> {
+    type Output = A::Output;
+
+    fn update(&mut self, trade: &Trade) {
+        // Out-of-order trades must not pull the window back in time
+        let newest = self
+            .newest
+            .map_or(trade.timestamp, |t| t.max(trade.timestamp));
+        self.newest = Some(newest);
+        self.inner.update(trade);
+        match self.window {
+            RollingWindow::Count(_) => self.trades.push_back(trade.clone()),
+            // Keep the window sorted by time so eviction from the front is exact
+            RollingWindow::Time(_) => {
+                let at = self
+                    .trades
+                    .partition_point(|t| t.timestamp <= trade.timestamp);
+                self.trades.insert(at, trade.clone());
+            }
+        }
+
+        loop {
+            let expired = match (self.window, self.trades.front()) {
+                (RollingWindow::Count(n), Some(_)) => self.trades.len() > n,
+                (RollingWindow::Time(d), Some(oldest)) => {
+                    oldest.timestamp <= newest.saturating_sub(d)
+                }
+                (_, None) => false,
+            };
+            if !expired {
+                break;
+            }
+            if let Some(old) = self.trades.pop_front() {
+                self.inner.remove(&old);
+            }
+        }
+    }
+
+    fn remove(&mut self, trade: &Trade) {
+        // Drop the oldest matching trade still in the window
+        let position = self.trades.iter().position(|t| {
+            t.timestamp == trade.timestamp
+                && t.price == trade.price
+                && t.quantity == trade.quantity
+                && t.side == trade.side
+        });
+        if let Some(old) = position.and_then(|i| self.trades.remove(i)) {
+            self.inner.remove(&old);
+        }
+    }
+
+    fn value(&self) -> A::Output {
+        self.inner.value()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::metrics::{calculate_cvd, calculate_delta};
+    use crate::tape::{calculate_aggression_ratio, calculate_trade_pressure, calculate_vwap};
+
+    fn trades() -> Vec<Trade> {
+        [
+            (dec!(100), dec!(1), Side::Buy, 0),
+            (dec!(101), dec!(2.5), Side::Sell, 3),
+            (dec!(99.5), dec!(0.7), Side::Unknown, 4),
+            (dec!(100.25), dec!(3), Side::Buy, 9),
+            (dec!(102), dec!(1.1), Side::Sell, 15),
+            (dec!(101.5), dec!(0.4), Side::Buy, 16),
+        ]
+        .into_iter()
+        .map(|(price, quantity, side, timestamp)| Trade {
+            price,
+            quantity,
+            side,
+            timestamp,
+        })
+        .collect()
+    }
+
+    #[test]
+    fn test_streaming_matches_batch() {
+        let trades = trades();
+        let mut vwap = StreamingVwap::default();
+        let mut delta = StreamingDelta::default();
+        let mut cvd = StreamingCvd::default();
+        let mut pressure = StreamingTradePressure::default();
+        let mut aggression = StreamingAggressionRatio::default();
+
+        assert_eq!(vwap.value(), None);
+        assert_eq!(aggression.value(), dec!(0.5));
+
+        for (i, trade) in trades.iter().enumerate() {
+            vwap.update(trade);
+            delta.update(trade);
+            cvd.update(trade);
+            pressure.update(trade);
+            aggression.update(trade);
+
+            let seen = &trades[..=i];
+            assert_eq!(vwap.value(), calculate_vwap(seen));
+            assert_eq!(delta.value(), calculate_delta(seen));
+            assert_eq!(cvd.value(), calculate_cvd(seen).last().copied());
+            assert_eq!(pressure.value(), calculate_trade_pressure(seen));
+            assert_eq!(aggression.value(), calculate_aggression_ratio(seen));
+        }
+    }
+
+    #[test]
+    fn test_rolling_by_count() {
+        let trades = trades();
+        let mut vwap: Rolling<StreamingVwap> = Rolling::by_count(3);
+        let mut aggression: Rolling<StreamingAggressionRatio> = Rolling::by_count(3);
+
+        for (i, trade) in trades.iter().enumerate() {
+            vwap.update(trade);
+            aggression.update(trade);
+
+            let window = &trades[i.saturating_sub(2)..=i];
+            assert_eq!(vwap.len(), window.len());
+            assert_eq!(vwap.value(), calculate_vwap(window));
+            assert_eq!(aggression.value(), calculate_aggression_ratio(window));
+        }
+    }
+
+    #[test]
+    fn test_rolling_by_time() {
+        let trades = trades();
+        let mut delta: Rolling<StreamingDelta> = Rolling::by_time(10);
+        let mut pressure: Rolling<StreamingTradePressure> = Rolling::by_time(10);
+
+        for (i, trade) in trades.iter().enumerate() {
+            delta.update(trade);
+            pressure.update(trade);
+
+            let window: Vec<Trade> = trades[..=i]
+                .iter()
+                .filter(|t| t.timestamp > trade.timestamp - 10)
+                .cloned()
+                .collect();
+            assert_eq!(delta.value(), calculate_delta(&window));
+            assert_eq!(pressure.value(), calculate_trade_pressure(&window));
+        }
+
+        // At t=16 only the trades at 9, 15 and 16 remain
+        assert_eq!(delta.len(), 3);
+        assert_eq!(delta.trades().next().map(|t| t.timestamp), Some(9));
+    }
+
+    #[test]
+    fn test_rolling_by_time_out_of_order() {
+        let trades = trades();
+        let mut delta: Rolling<StreamingDelta> = Rolling::by_time(10);
+
+        // A late trade at t=4 arrives after t=16
+        for trade in [&trades[0], &trades[4], &trades[5], &trades[2]] {
+            delta.update(trade);
+        }
+        assert_eq!(delta.len(), 2);
+
+        // Eviction follows t=16, not the late trade
+        for trade in [&trades[3], &trades[1]] {
+            delta.update(trade);
+        }
+        let timestamps: Vec<i64> = delta.trades().map(|t| t.timestamp).collect();
+        assert_eq!(timestamps, vec![9, 15, 16]);
+        assert_eq!(delta.value(), calculate_delta(&trades[3..]));
+    }
+}
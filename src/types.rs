@@ -1,5 +1,7 @@
 use rust_decimal::Decimal;
-use serde::{Deserialize, Serialize};
+use serde::{de, Deserialize, Deserializer, Serialize};
+use std::fmt;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Level {
@@ -14,10 +16,150 @@ pub struct OrderBook {
     pub timestamp: i64,
 }
 
+/// Aggressor side of a trade
+///
+/// Deserialization is case-insensitive and accepts common exchange
+/// spellings (`"b"`, `"bid"`, `"s"`, `"ask"`, `"offer"`, ...) as well as
+/// `1`/`-1` as strings or integers. `null`, a missing field, an empty string,
+/// `"unknown"`, `"?"` or `0` become [`Side::Unknown`]; any other value is an error,
+/// so a misspelled side is not silently dropped from buy/sell statistics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Side {
+    /// Buyer-initiated (lifted the offer)
+    Buy,
+    /// Seller-initiated (hit the bid)
+    Sell,
+    /// No aggressor information available
+    #[default]
+    Unknown,
+}
+
+impl Side {
+    /// The opposite side (`Unknown` stays `Unknown`)
+    #[must_use]
+    pub fn opposite(self) -> Side {
+        match self {
+            Side::Buy => Side::Sell,
+            Side::Sell => Side::Buy,
+            Side::Unknown => Side::Unknown,
+        }
+    }
+
+    /// Whether the aggressor is known
+    #[must_use]
+    pub fn is_known(self) -> bool {
+        self != Side::Unknown
+    }
+}
+
+impl fmt::Display for Side {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Side::Buy => "buy",
+            Side::Sell => "sell",
+            Side::Unknown => "unknown",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Error returned when a trade side is not recognized
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseSideError {
+    pub value: String,
+}
+
+impl fmt::Display for ParseSideError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unrecognized trade side '{}'", self.value)
+    }
+}
+
+impl std::error::Error for ParseSideError {}
+
+impl FromStr for Side {
+    type Err = ParseSideError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "buy" | "b" | "bid" | "bot" | "bought" | "long" | "1" => Ok(Side::Buy),
+            "sell" | "s" | "ask" | "offer" | "a" | "sld" | "sold" | "short" | "-1" => {
+                Ok(Side::Sell)
+            }
+            "" | "unknown" | "u" | "?" | "0" => Ok(Side::Unknown),
+            _ => Err(ParseSideError {
+                value: s.to_string(),
+            }),
+        }
+    }
+}
+
+/// Side as it appears on the wire: a string or a signed integer
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum RawSide {
+    Number(i64),
+    Text(String),
+}
+
+impl<'de> Deserialize<'de> for Side {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        match Option::<RawSide>::deserialize(deserializer)? {
+            None => Ok(Side::Unknown),
+            Some(RawSide::Number(1)) => Ok(Side::Buy),
+            Some(RawSide::Number(-1)) => Ok(Side::Sell),
+            Some(RawSide::Number(0)) => Ok(Side::Unknown),
+            Some(RawSide::Number(n)) => Err(de::Error::custom(ParseSideError {
+                value: n.to_string(),
+            })),
+            Some(RawSide::Text(s)) => s.parse().map_err(de::Error::custom),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub price: Decimal,
     pub quantity: Decimal,
-    pub side: String,
+    #[serde(default)]
+    pub side: Side,
     pub timestamp: i64,
 }
+
+impl Trade {
+    /// Quantity signed by aggressor: positive for buys, negative for sells,
+    /// zero when the side is unknown
+    #[must_use]
+    pub fn signed_quantity(&self) -> Decimal {
+        match self.side {
+            Side::Buy => self.quantity,
+            Side::Sell => -self.quantity,
+            Side::Unknown => Decimal::ZERO,
+        }
+    }
+}
+
+/// A trade with the buyer's and seller's account ids
+///
+/// Only surveillance needs the ids, so they live here rather than on
+/// [`Trade`]. Either id is `None` when the feed does not provide it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountedTrade {
+    #[serde(flatten)]
+    pub trade: Trade,
+    #[serde(default)]
+    pub buyer_id: Option<String>,
+    #[serde(default)]
+    pub seller_id: Option<String>,
+}
+
+impl From<Trade> for AccountedTrade {
+    fn from(trade: Trade) -> Self {
+        Self {
+            trade,
+            buyer_id: None,
+            seller_id: None,
+        }
+    }
+}
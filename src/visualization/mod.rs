@@ -3,7 +3,8 @@
 //! This module provides utilities for visualizing market data.
 //! Currently provides text-based visualization, with plans for graphical output.
 
-use crate::types::{OrderBook, Trade};
+use crate::metrics::{FootprintBar, TpoProfile};
+use crate::types::{OrderBook, Side, Trade};
 use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use std::fmt::Write;
@@ -46,10 +47,10 @@ pub fn print_trades(trades: &[Trade], limit: usize) {
     println!("{}", "─".repeat(50));
 
     for trade in trades.iter().take(limit) {
-        let side_symbol = if trade.side == "buy" {
-            "🟢 BUY"
-        } else {
-            "🔴 SELL"
+        let side_symbol = match trade.side {
+            Side::Buy => "🟢 BUY",
+            Side::Sell => "🔴 SELL",
+            Side::Unknown => "⚪ ???",
         };
         println!(
             "{:<12} ${:<11} {:<12} {}",
@@ -115,6 +116,127 @@ pub fn ascii_depth_chart(orderbook: &OrderBook, depth: usize) -> String {
     output
 }
 
+/// Render a footprint bar as a bid x ask grid, highest price first
+///
+/// `▼` marks a sell imbalance, `▲` a buy imbalance and `◀` the POC.
+#[must_use]
+pub fn footprint_grid(footprint: &FootprintBar) -> String {
+    let mut output = String::new();
+    let bar = &footprint.bar;
+
+    let _ = writeln!(
+        output,
+        "Footprint {} → {}  O:{} H:{} L:{} C:{}  Δ {}",
+        bar.start, bar.end, bar.open, bar.high, bar.low, bar.close, bar.delta
+    );
+    let _ = writeln!(
+        output,
+        "{:>12} │ {:>10}   {:<10} │ {:>10}",
+        "Price", "Bid", "Ask", "Delta"
+    );
+    let _ = writeln!(output, "{}", "─".repeat(52));
+
+    for level in &footprint.levels {
+        let sell_mark = if level.sell_imbalance { "▼" } else { " " };
+        let buy_mark = if level.buy_imbalance { "▲" } else { " " };
+        let poc_mark = if footprint.poc == Some(level.price) {
+            " ◀"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            output,
+            "{:>12} │{sell_mark}{:>10} x {:<10}{buy_mark}│ {:>10}{poc_mark}",
+            level.price, level.sell_volume, level.buy_volume, level.delta
+        );
+    }
+
+    for stack in &footprint.stacked_imbalances {
+        let _ = writeln!(
+            output,
+            "Stacked {} imbalance: {} - {} ({} levels)",
+            stack.side, stack.low, stack.high, stack.levels
+        );
+    }
+
+    output
+}
+
+/// Print footprint bars in text format
+pub fn print_footprints(footprints: &[FootprintBar]) {
+    for footprint in footprints {
+        println!("{}", footprint_grid(footprint));
+    }
+}
+
+/// Render a market profile, highest price first
+///
+/// `│` marks prices inside the value area and `◀` the POC.
+#[must_use]
+pub fn tpo_chart(profile: &TpoProfile) -> String {
+    let mut output = String::new();
+    let width = profile
+        .levels
+        .iter()
+        .map(|l| l.letters.len())
+        .max()
+        .unwrap_or(0);
+
+    let _ = writeln!(
+        output,
+        "Market Profile {} ({} periods of {})  {:?}",
+        profile.start, profile.periods, profile.period, profile.shape
+    );
+    let _ = writeln!(output, "{}", "─".repeat(20 + width));
+
+    let in_value_area = |price| match (profile.val, profile.vah) {
+        (Some(val), Some(vah)) => price >= val && price <= vah,
+        _ => false,
+    };
+    for level in &profile.levels {
+        let va_mark = if in_value_area(level.price) {
+            "│"
+        } else {
+            " "
+        };
+        let poc_mark = if profile.poc == Some(level.price) {
+            " ◀ POC"
+        } else {
+            ""
+        };
+        let _ = writeln!(
+            output,
+            "{:>12} {va_mark} {:<width$}{poc_mark}",
+            level.price, level.letters
+        );
+    }
+
+    if let Some((low, high)) = profile.initial_balance {
+        let _ = writeln!(output, "Initial balance: {low} - {high}");
+    }
+    if !profile.single_prints.is_empty() {
+        let prices: Vec<String> = profile
+            .single_prints
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        let _ = writeln!(output, "Single prints: {}", prices.join(", "));
+    }
+    if profile.poor_high {
+        let _ = writeln!(output, "Poor high");
+    }
+    if profile.poor_low {
+        let _ = writeln!(output, "Poor low");
+    }
+
+    output
+}
+
+/// Print a market profile in text format
+pub fn print_tpo_profile(profile: &TpoProfile) {
+    println!("{}", tpo_chart(profile));
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -153,6 +275,63 @@ mod tests {
         assert!(chart.contains("BID"));
     }
 
+    #[test]
+    fn test_footprint_grid() {
+        use crate::bars::BarSpec;
+        use crate::metrics::{build_footprints, FootprintConfig};
+
+        let trades: Vec<Trade> = [
+            (dec!(100), dec!(1), Side::Sell),
+            (dec!(101), dec!(5), Side::Buy),
+            (dec!(102), dec!(2), Side::Buy),
+        ]
+        .into_iter()
+        .enumerate()
+        .map(|(i, (price, quantity, side))| Trade {
+            price,
+            quantity,
+            side,
+            timestamp: i as i64,
+        })
+        .collect();
+
+        let footprints = build_footprints(&trades, BarSpec::Tick(3), FootprintConfig::default());
+        let grid = footprint_grid(&footprints[0]);
+        let lines: Vec<&str> = grid.lines().collect();
+
+        assert!(lines[0].starts_with("Footprint 0 → 2"));
+        assert!(lines[3].trim_start().starts_with("102"));
+        assert!(lines[4].contains("▲"));
+        assert!(lines[4].ends_with("◀"));
+    }
+
+    #[test]
+    fn test_tpo_chart() {
+        use crate::metrics::{build_tpo_profile, TpoConfig};
+
+        let trades: Vec<Trade> = [(dec!(100), 0), (dec!(102), 10), (dec!(101), 30)]
+            .into_iter()
+            .map(|(price, timestamp)| Trade {
+                price,
+                quantity: dec!(1),
+                side: Side::Unknown,
+                timestamp,
+            })
+            .collect();
+        let config = TpoConfig {
+            period: 30,
+            ..TpoConfig::default()
+        };
+
+        let chart = tpo_chart(&build_tpo_profile(&trades, &config));
+        let lines: Vec<&str> = chart.lines().collect();
+
+        assert!(lines[0].starts_with("Market Profile 0 (2 periods of 30)"));
+        assert_eq!(lines[2].trim(), "102 │ A");
+        assert!(lines[3].ends_with("AB ◀ POC"));
+        assert!(chart.contains("Initial balance: 100 - 102"));
+    }
+
     #[test]
     fn test_ascii_depth_chart_empty() {
         let empty_ob = OrderBook {
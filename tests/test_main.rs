@@ -18,3 +18,128 @@ mod tests {
         assert_eq!(result, 4, "Basic arithmetic should work");
     }
 }
+
+use market_microstructure_analyzer::*;
+use rust_decimal_macros::dec;
+use std::process::Command;
+
+#[test]
+fn test_trade_side_aliases() {
+    let json = r#"[
+        {"price": "100", "quantity": "1", "side": "BUY", "timestamp": 1},
+        {"price": "100", "quantity": "1", "side": "b", "timestamp": 2},
+        {"price": "100", "quantity": "1", "side": "Ask", "timestamp": 3},
+        {"price": "100", "quantity": "1", "side": "offer", "timestamp": 4},
+        {"price": "100", "quantity": "1", "side": null, "timestamp": 5},
+        {"price": "100", "quantity": "1", "timestamp": 6},
+        {"price": "100", "quantity": "1", "side": "?", "timestamp": 7},
+        {"price": "100", "quantity": "1", "side": 1, "timestamp": 8},
+        {"price": "100", "quantity": "1", "side": -1, "timestamp": 9}
+    ]"#;
+
+    let trades: Vec<Trade> = serde_json::from_str(json).unwrap();
+    let sides: Vec<Side> = trades.iter().map(|t| t.side).collect();
+
+    assert_eq!(
+        sides,
+        vec![
+            Side::Buy,
+            Side::Buy,
+            Side::Sell,
+            Side::Sell,
+            Side::Unknown,
+            Side::Unknown,
+            Side::Unknown,
+            Side::Buy,
+            Side::Sell
+        ]
+    );
+    assert_eq!(serde_json::to_string(&Side::Sell).unwrap(), r#""sell""#);
+
+    // Misspelled sides are rejected rather than counted as unknown
+    let misspelled = r#"{"price": "100", "quantity": "1", "side": "bye", "timestamp": 1}"#;
+    assert!(serde_json::from_str::<Trade>(misspelled).is_err());
+    assert!(serde_json::from_str::<Side>("2").is_err());
+}
+
+#[test]
+fn test_unknown_side_is_not_selling_pressure() {
+    let trades = vec![
+        Trade {
+            price: dec!(100),
+            quantity: dec!(2),
+            side: Side::Buy,
+            timestamp: 1,
+        },
+        Trade {
+            price: dec!(100),
+            quantity: dec!(5),
+            side: Side::Unknown,
+            timestamp: 2,
+        },
+    ];
+
+    assert_eq!(metrics::calculate_delta(&trades), dec!(2));
+    assert_eq!(
+        tape::calculate_trade_pressure(&trades),
+        (dec!(2), dec!(0), dec!(2))
+    );
+}
+
+#[test]
+fn test_cli_profile_zero_volume() {
+    let path = std::env::temp_dir().join(format!("zero-volume-{}.csv", std::process::id()));
+    std::fs::write(
+        &path,
+        "price,quantity,side,timestamp\n100,0,buy,1\n101,0,sell,2\n",
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_market-analyzer"))
+        .arg("profile")
+        .arg("--trades")
+        .arg(&path)
+        .output()
+        .unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("POC"));
+    assert!(!stdout.contains('█'));
+}
+
+#[test]
+fn test_cli_detect_fill_icebergs_are_opt_in() {
+    let path = std::env::temp_dir().join(format!("fills-{}.csv", std::process::id()));
+    std::fs::write(
+        &path,
+        "price,quantity,side,timestamp\n100,1,buy,1\n100,1,buy,2\n100,1,buy,3\n",
+    )
+    .unwrap();
+
+    let detect = |extra: &[&str]| {
+        Command::new(env!("CARGO_BIN_EXE_market-analyzer"))
+            .args(["detect", "--format", "csv", "--trades"])
+            .arg(&path)
+            .args(extra)
+            .output()
+            .unwrap()
+    };
+    let default = detect(&[]);
+    let with_fills = detect(&["--min-fills", "3"]);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(default.status.success());
+    assert!(!String::from_utf8_lossy(&default.stdout).contains("iceberg"));
+    assert!(
+        with_fills.status.success(),
+        "{}",
+        String::from_utf8_lossy(&with_fills.stderr)
+    );
+    assert!(String::from_utf8_lossy(&with_fills.stdout).contains("iceberg"));
+}